@@ -5,7 +5,12 @@ use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
 use rodio::source::{SineWave, TakeDuration};
-use gilrs::{Gilrs, Button};
+use gilrs::Button;
+
+use crate::gamepad::Gamepads;
+
+// Music volume until the settings say otherwise
+pub const DEFAULT_VOLUME: f32 = 0.2;
 
 pub struct AudioManager {
     pub audio_enabled: bool,
@@ -13,6 +18,7 @@ pub struct AudioManager {
     stream_handle: OutputStreamHandle,
     music_sink: Arc<Mutex<Option<Sink>>>,
     current_track: usize,
+    volume: f32,
     taylor_swift_tracks: Vec<&'static str>,
 }
 
@@ -45,6 +51,7 @@ impl AudioManager {
             stream_handle,
             music_sink: Arc::new(Mutex::new(None)),
             current_track: 0,
+            volume: DEFAULT_VOLUME,
             taylor_swift_tracks,
         })
     }
@@ -76,7 +83,7 @@ impl AudioManager {
             }
             
             // Hacer que la melodía se repita
-            sink.set_volume(0.2);
+            sink.set_volume(self.volume);
             
             let mut music_sink = self.music_sink.lock().unwrap();
             *music_sink = Some(sink);
@@ -292,40 +299,23 @@ impl AudioManager {
         }
     }
     
-    pub fn set_volume(&self, volume: f32) {
+    // Also applies to the tracks played after this one
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
         if let Ok(sink_guard) = self.music_sink.lock() {
             if let Some(ref sink) = *sink_guard {
-                sink.set_volume(volume.clamp(0.0, 1.0));
+                sink.set_volume(self.volume);
             }
         }
     }
     
-    pub fn handle_gamepad_controls(&mut self, gilrs: &Gilrs) -> Result<(), Box<dyn std::error::Error>> {
-        // Check for gamepad button presses for audio controls
-        for (_id, gamepad) in gilrs.gamepads() {
-            // Right shoulder button (RB/R1) for next track
-            if gamepad.is_pressed(Button::RightTrigger2) {
-                return self.next_track();
-            }
-            
-            // Left shoulder button (LB/L1) for previous track
-            if gamepad.is_pressed(Button::LeftTrigger2) {
-                return self.previous_track();
-            }
-            
-            // Start button for toggle audio
-            if gamepad.is_pressed(Button::Start) {
-                self.toggle_audio();
-                return Ok(());
-            }
-            
-            // Select/Back button for volume control (could implement volume adjustment)
-            if gamepad.is_pressed(Button::Select) {
-                // Could cycle through volume levels here
-                self.set_volume(0.5); // Set to medium volume
-                println!("🎮 Volume set to 50%");
-                return Ok(());
-            }
+    pub fn handle_gamepad_controls(&mut self, gamepads: &Gamepads) -> Result<(), Box<dyn std::error::Error>> {
+        // Next/previous track and audio on/off are the NextTrack, PreviousTrack and ToggleAudio actions
+        // Select/Back button for volume control (could implement volume adjustment)
+        if gamepads.pressed(Button::Select) {
+            // Could cycle through volume levels here
+            self.set_volume(0.5); // Set to medium volume
+            println!("🎮 Volume set to 50%");
         }
         
         Ok(())
@@ -0,0 +1,237 @@
+//! Capturas de pantalla (F12) y grabación de secuencias de frames (F9).
+//!
+//! Aquí se decide qué frames guardar y con qué nombre. El framebuffer devuelve el frame
+//! terminado, con el HUD y el minimapa, y `Capture::save` lo pasa a un hilo aparte que lo
+//! codifica como PNG: codificar en el hilo principal trababa el juego mientras se grababa.
+//! Cada archivo se informa recién cuando está escrito, o con un aviso si no se pudo.
+
+use raylib::prelude::Image;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::thread::{self, JoinHandle};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const CAPTURE_DIR: &str = "captures";
+// While recording, one frame out of this many is saved (15 images/s at 60 FPS)
+pub const RECORD_EVERY: u32 = 4;
+// Frames waiting for the encoder; past this the game waits instead of piling up memory
+const QUEUED_FRAMES: usize = 8;
+const SCREENSHOT_PREFIX: &str = "captura_";
+
+// A frame read back from the screen. It owns its CPU-side pixels and exporting or freeing it
+// never touches the GL context, so it can be encoded on another thread.
+struct Frame(Image);
+unsafe impl Send for Frame {}
+
+struct Writer {
+    frames: SyncSender<(Frame, Vec<PathBuf>)>,
+    thread: JoinHandle<()>,
+}
+
+struct Recording {
+    dir: PathBuf,
+    frames_seen: u32,
+    saved: u32,
+}
+
+pub struct Capture {
+    dir: PathBuf,
+    every: u32,
+    screenshot_requested: bool,
+    recording: Option<Recording>,
+    writer: Option<Writer>, // Started with the first saved frame
+}
+
+impl Capture {
+    pub fn new(dir: impl Into<PathBuf>, every: u32) -> Self {
+        Capture { dir: dir.into(), every: every.max(1), screenshot_requested: false, recording: None, writer: None }
+    }
+
+    // The next composed frame is saved as captura_<fecha>.png
+    pub fn request_screenshot(&mut self) {
+        self.screenshot_requested = true;
+    }
+
+    /// Empieza una secuencia nueva en `secuencia_<fecha>/` o termina la actual.
+    /// Devuelve la carpeta de la secuencia al empezar.
+    pub fn toggle_recording(&mut self, now: SystemTime) -> Option<&Path> {
+        if let Some(recording) = self.recording.take() {
+            println!("⏹️ Grabación terminada: {} frames en {}", recording.saved, recording.dir.display());
+            return None;
+        }
+        let dir = self.dir.join(format!("secuencia_{}", timestamp(now)));
+        let recording = self.recording.insert(Recording { dir, frames_seen: 0, saved: 0 });
+        Some(&recording.dir)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Archivos donde guardar el frame actual: la captura pedida y/o el siguiente de la secuencia.
+    /// Las carpetas se crean aquí; si no se puede, ese archivo se omite con un aviso.
+    pub fn frame_targets(&mut self, now: SystemTime) -> Vec<PathBuf> {
+        let mut targets = Vec::new();
+        if std::mem::take(&mut self.screenshot_requested) {
+            targets.push(self.dir.join(format!("{}{}.png", SCREENSHOT_PREFIX, timestamp(now))));
+        }
+        if let Some(recording) = &mut self.recording {
+            if recording.frames_seen.is_multiple_of(self.every) {
+                targets.push(recording.dir.join(format!("frame_{:05}.png", recording.saved)));
+                recording.saved += 1;
+            }
+            recording.frames_seen += 1;
+        }
+        targets.retain(|path| match path.parent().map(fs::create_dir_all) {
+            Some(Err(e)) => {
+                println!("⚠️ No se pudo crear la carpeta de {}: {}", path.display(), e);
+                false
+            }
+            _ => true,
+        });
+        targets
+    }
+
+    /// Guarda `image` en cada uno de `targets` desde el hilo de escritura.
+    pub fn save(&mut self, image: Image, targets: Vec<PathBuf>) {
+        if targets.is_empty() {
+            return;
+        }
+        let writer = self.writer.get_or_insert_with(spawn_writer);
+        if writer.frames.send((Frame(image), targets)).is_err() {
+            println!("⚠️ El hilo de capturas terminó; no se guardó el frame");
+        }
+    }
+}
+
+impl Drop for Capture {
+    // Frames still queued are written before the game closes
+    fn drop(&mut self) {
+        if let Some(Writer { frames, thread }) = self.writer.take() {
+            drop(frames);
+            let _ = thread.join();
+        }
+    }
+}
+
+fn spawn_writer() -> Writer {
+    let (frames, queue) = sync_channel::<(Frame, Vec<PathBuf>)>(QUEUED_FRAMES);
+    let thread = thread::spawn(move || {
+        for (Frame(image), targets) in queue {
+            for path in targets {
+                image.export_image(&path.to_string_lossy());
+                match check_written(&path) {
+                    Ok(()) if is_screenshot(&path) => println!("📸 Captura guardada en {}", path.display()),
+                    Ok(()) => {}
+                    Err(e) => println!("⚠️ No se pudo guardar {}: {}", path.display(), e),
+                }
+            }
+        }
+    });
+    Writer { frames, thread }
+}
+
+// raylib's export doesn't report errors, so look at what ended up on disk
+fn check_written(path: &Path) -> Result<(), String> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.len() > 0 => Ok(()),
+        Ok(_) => Err("el archivo quedó vacío".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn is_screenshot(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name.to_string_lossy().starts_with(SCREENSHOT_PREFIX))
+}
+
+/// Fecha y hora UTC para nombres de archivo: `AAAAMMDD_HHMMSS_mmm`.
+pub fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let seconds_of_day = secs % 86_400;
+    format!(
+        "{:04}{:02}{:02}_{:02}{:02}{:02}_{:03}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+// Days since 1970-01-01 to (year, month, day), proleptic Gregorian calendar
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(secs: u64, millis: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn timestamps_are_utc_calendar_dates() {
+        assert_eq!(timestamp(at(0, 0)), "19700101_000000_000");
+        // 2024-02-29 23:59:59.042, a leap day
+        assert_eq!(timestamp(at(1_709_251_199, 42)), "20240229_235959_042");
+        assert_eq!(timestamp(at(1_709_251_200, 0)), "20240301_000000_000");
+    }
+
+    #[test]
+    fn only_files_on_disk_count_as_written() {
+        let dir = std::env::temp_dir().join("proy1_capture_written");
+        fs::create_dir_all(&dir).unwrap();
+        let (full, empty) = (dir.join("captura_lleno.png"), dir.join("frame_00000.png"));
+        fs::write(&full, [137, 80, 78, 71]).unwrap();
+        fs::write(&empty, []).unwrap();
+        assert!(check_written(&full).is_ok());
+        assert!(check_written(&empty).is_err());
+        assert!(check_written(&dir.join("nunca_escrito.png")).is_err());
+        assert!(is_screenshot(&full) && !is_screenshot(&empty));
+    }
+
+    #[test]
+    fn screenshot_is_taken_once() {
+        let dir = std::env::temp_dir().join("proy1_capture_screenshot");
+        let mut capture = Capture::new(&dir, 4);
+        assert!(capture.frame_targets(at(0, 0)).is_empty());
+        capture.request_screenshot();
+        assert_eq!(capture.frame_targets(at(0, 0)), vec![dir.join("captura_19700101_000000_000.png")]);
+        assert!(capture.frame_targets(at(0, 0)).is_empty());
+    }
+
+    #[test]
+    fn recording_keeps_every_nth_frame_numbered() {
+        let dir = std::env::temp_dir().join("proy1_capture_recording");
+        let mut capture = Capture::new(&dir, 3);
+        let sequence = capture.toggle_recording(at(60, 0)).unwrap().to_path_buf();
+        assert_eq!(sequence, dir.join("secuencia_19700101_000100_000"));
+
+        let saved: Vec<Vec<PathBuf>> = (0..7).map(|_| capture.frame_targets(at(61, 0))).collect();
+        let names: Vec<usize> = saved.iter().map(Vec::len).collect();
+        assert_eq!(names, [1, 0, 0, 1, 0, 0, 1]);
+        assert_eq!(saved[6], vec![sequence.join("frame_00002.png")]);
+        assert!(sequence.is_dir());
+
+        assert!(capture.toggle_recording(at(62, 0)).is_none());
+        assert!(!capture.is_recording());
+        assert!(capture.frame_targets(at(63, 0)).is_empty());
+    }
+}
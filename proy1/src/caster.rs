@@ -1,68 +1,201 @@
 use raylib::color::Color;
+use raylib::prelude::Vector2;
 
 use crate::framebuffer::Framebuffer;
-use crate::maze::Maze;
+use crate::line::line;
+use crate::maze::is_wall_cell;
 use crate::player::Player;
 
+// Side of the wall cell that the ray struck (named after the direction it faces)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WallFace {
+    North,
+    South,
+    East,
+    West,
+}
+
 pub struct Intersect {
     pub distance: f32,
     pub impact: char,
     pub tx: f32, // Texture X coordinate (0.0 to 1.0)
+    pub hit: Vector2, // Exact world-space point where the ray met the wall
+    pub face: WallFace,
 }
 
 pub fn cast_ray(
     framebuffer: &mut Framebuffer,
-    maze: &Vec<Vec<char>>,
+    maze: &[Vec<char>],
     player: &Player,
     a: f32,
     block_size: usize,
     draw_line: bool,
 ) -> Intersect {
-    let mut d = 0.0;
+    let intersect = trace_ray(maze, player.pos, a, block_size);
+
+    if draw_line {
+        framebuffer.set_current_color(Color::WHITESMOKE);
+        line(framebuffer, player.pos, intersect.hit);
+    }
+
+    intersect
+}
+
+/// Recorre la cuadrícula celda por celda (DDA) hasta encontrar una pared.
+///
+/// `origin` está en coordenadas de mundo y cada celda mide `block_size` unidades.
+/// Salir del mapa cuenta como chocar con una pared '+' en el borde.
+pub fn trace_ray(maze: &[Vec<char>], origin: Vector2, a: f32, block_size: usize) -> Intersect {
+    let block = block_size as f32;
+    let dir_x = a.cos();
+    let dir_y = a.sin();
+
+    // Work in cell units so every boundary crossing is one step
+    let pos_x = origin.x / block;
+    let pos_y = origin.y / block;
+    let mut cell_x = pos_x.floor() as i64;
+    let mut cell_y = pos_y.floor() as i64;
 
-    framebuffer.set_current_color(Color::WHITESMOKE);
+    // Ray length (in cells) needed to cross one full cell on each axis
+    let delta_x = if dir_x == 0.0 { f32::INFINITY } else { (1.0 / dir_x).abs() };
+    let delta_y = if dir_y == 0.0 { f32::INFINITY } else { (1.0 / dir_y).abs() };
+
+    let (step_x, mut side_x) = if dir_x < 0.0 {
+        (-1, (pos_x - cell_x as f32) * delta_x)
+    } else {
+        (1, (cell_x as f32 + 1.0 - pos_x) * delta_x)
+    };
+    let (step_y, mut side_y) = if dir_y < 0.0 {
+        (-1, (pos_y - cell_y as f32) * delta_y)
+    } else {
+        (1, (cell_y as f32 + 1.0 - pos_y) * delta_y)
+    };
 
     loop {
-        let cos = d * a.cos();
-        let sin = d * a.sin();
-        let x = player.pos.x + cos;
-        let y = player.pos.y + sin;
-
-        let i = (x / block_size as f32) as usize;
-        let j = (y / block_size as f32) as usize;
-
-        if j >= maze.len() || i >= maze[0].len() {
-            return Intersect {
-                distance: d,
-                impact: '+',
-                tx: 0.0,
-            };
-        }
+        // Advance to whichever cell boundary is closer
+        let (t, crossed_x) = if side_x < side_y {
+            cell_x += step_x;
+            let t = side_x;
+            side_x += delta_x;
+            (t, true)
+        } else {
+            cell_y += step_y;
+            let t = side_y;
+            side_y += delta_y;
+            (t, false)
+        };
 
-        if maze[j][i] == '+' || maze[j][i] == '-' || maze[j][i] == '|' {
-            // Calculate texture X coordinate
-            let block_x = i as f32 * block_size as f32;
-            let block_y = j as f32 * block_size as f32;
-            
-            let tx = if maze[j][i] == '|' {
-                // Vertical wall: use Y position within the block
-                (y - block_y) / block_size as f32
-            } else {
-                // Horizontal wall: use X position within the block
-                (x - block_x) / block_size as f32
-            };
-            
-            return Intersect {
-                distance: d,
-                impact: maze[j][i],
-                tx: tx.fract(), // Keep only fractional part for 0.0-1.0 range
-            };
-        }
+        let cell = if cell_y < 0 || cell_x < 0 {
+            None
+        } else {
+            maze.get(cell_y as usize).and_then(|row| row.get(cell_x as usize)).copied()
+        };
+
+        let impact = match cell {
+            Some(c) if is_wall_cell(c) => c,
+            Some(_) => continue,
+            None => '+', // Outside the map behaves like a solid border
+        };
+
+        let hit_x = pos_x + dir_x * t;
+        let hit_y = pos_y + dir_y * t;
+
+        // Keep the texture running left-to-right as seen from in front of the face
+        let (face, tx) = if crossed_x {
+            let frac = hit_y - hit_y.floor();
+            if step_x > 0 { (WallFace::West, frac) } else { (WallFace::East, 1.0 - frac) }
+        } else {
+            let frac = hit_x - hit_x.floor();
+            if step_y > 0 { (WallFace::North, 1.0 - frac) } else { (WallFace::South, frac) }
+        };
+
+        return Intersect {
+            distance: t * block,
+            impact,
+            tx: tx.clamp(0.0, 1.0),
+            hit: Vector2::new(hit_x * block, hit_y * block),
+            face,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Maze;
+    use std::f32::consts::PI;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn hits_east_wall_at_exact_distance() {
+        let maze = Maze::from_rows(&["+---+", "|   |", "+---+"]).map;
+        // Center of cell (1,1) with block size 20 is (30, 30); east wall starts at x = 80
+        let hit = trace_ray(&maze, Vector2::new(30.0, 30.0), 0.0, 20);
+        assert_close(hit.distance, 50.0);
+        assert_eq!(hit.impact, '|');
+        assert_eq!(hit.face, WallFace::West);
+        assert_close(hit.hit.x, 80.0);
+        assert_close(hit.hit.y, 30.0);
+        assert_close(hit.tx, 0.5);
+    }
+
+    #[test]
+    fn hits_each_face_from_inside_a_room() {
+        let maze = Maze::from_rows(&["+---+", "|   |", "|   |", "+---+"]).map;
+        let origin = Vector2::new(50.0, 45.0);
+
+        let west = trace_ray(&maze, origin, PI, 20);
+        assert_close(west.distance, 30.0);
+        assert_eq!(west.face, WallFace::East);
 
-        if draw_line {
-            framebuffer.set_pixel(x as u32, y as u32);
+        let south = trace_ray(&maze, origin, PI / 2.0, 20);
+        assert_close(south.distance, 15.0);
+        assert_eq!(south.face, WallFace::North);
+        assert_eq!(south.impact, '-');
+
+        let north = trace_ray(&maze, origin, -PI / 2.0, 20);
+        assert_close(north.distance, 25.0);
+        assert_eq!(north.face, WallFace::South);
+    }
+
+    #[test]
+    fn diagonal_ray_does_not_slip_through_corner() {
+        // Two walls touching only at a corner; the old unit-step caster could pass between them
+        let maze = Maze::from_rows(&["+++++", "+ + +", "++  +", "+++++"]).map;
+        let hit = trace_ray(&maze, Vector2::new(30.0, 30.0), PI / 4.0, 20);
+        assert_eq!(hit.impact, '+');
+        assert_close(hit.distance, 10.0 * 2f32.sqrt());
+    }
+
+    #[test]
+    fn distance_is_continuous_along_a_wall() {
+        let maze = Maze::from_rows(&["+-----+", "|     |", "+-----+"]).map;
+        let origin = Vector2::new(20.0, 30.0);
+        // Shallow angles toward the bottom wall: distance = 10 / sin(a), never quantized
+        for step in 1..20 {
+            let a = step as f32 * 0.02 + 0.3;
+            let hit = trace_ray(&maze, origin, a, 20);
+            assert_eq!(hit.face, WallFace::North);
+            assert_close(hit.distance, 10.0 / a.sin());
         }
+    }
+
+    #[test]
+    fn leaving_the_map_reports_border() {
+        let maze = Maze::from_rows(&["   ", "   "]).map;
+        let hit = trace_ray(&maze, Vector2::new(10.0, 10.0), 0.0, 20);
+        assert_eq!(hit.impact, '+');
+        assert_close(hit.distance, 50.0);
+    }
 
-        d += 1.0;
+    #[test]
+    fn handles_ragged_rows() {
+        let maze = Maze::from_rows(&["+--+", "|  ", "+--+"]).map;
+        let hit = trace_ray(&maze, Vector2::new(30.0, 30.0), 0.0, 20);
+        assert_eq!(hit.impact, '+');
+        assert_close(hit.distance, 30.0);
     }
 }
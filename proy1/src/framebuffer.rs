@@ -1,39 +1,206 @@
 use raylib::prelude::*;
+use std::num::NonZeroUsize;
+use std::thread;
 use crate::player::Player;
+use crate::maze::{is_wall_cell, GOAL_DOOR, WORLD_BLOCK_SIZE};
+use crate::npc::Npc;
+use crate::ui::Layout;
+
+// Per-frame overlay data drawn on top of the 3D/2D view
+pub struct Hud<'a> {
+    pub fps: f32,
+    pub track_info: Option<&'a str>,
+    pub breadcrumbs: &'a [(usize, usize)], // Hint trail, empty when no hint is active
+    pub captured: usize,
+    pub total_pokemon: usize,
+    pub goal_locked: bool, // On the goal but the level wants every Pokémon first
+    pub lives: u32,
+    pub npcs: &'a [Npc],
+    pub recording: bool, // Frame sequence being saved; the marker itself stays out of the images
+    pub key_hint: &'a str, // Race players' keys for the help line; unused outside the race
+}
 
 pub struct Framebuffer {
     pub width: u32,
     pub height: u32,
-    pub color_buffer: Image,
+    // RGBA bytes, row by row; the same layout the GPU texture expects
+    pixels: Vec<[u8; 4]>,
+    // Created on the first swap and then only updated in place
+    texture: Option<Texture2D>,
     background_color: Color,
     current_color: Color,
+    // Column-major scratch for render_columns, kept between frames
+    columns: Vec<[u8; 4]>,
+    // Worker threads for render_columns; 1 renders on the calling thread
+    render_threads: usize,
+}
+
+// Name tag of each player in the split-screen race
+pub const RACER_COLORS: [Color; 2] = [Color::RED, Color::SKYBLUE];
+
+pub fn rgba(color: Color) -> [u8; 4] {
+    [color.r, color.g, color.b, color.a]
 }
 
 impl Framebuffer {
     pub fn new(width: u32, height: u32) -> Self {
-        let color_buffer = Image::gen_image_color(width as i32, height as i32, Color::BLACK);
         Framebuffer {
             width,
             height,
-            color_buffer,
+            pixels: vec![rgba(Color::BLACK); (width * height) as usize],
+            texture: None,
             background_color: Color::BLACK,
             current_color: Color::WHITE,
+            columns: Vec::new(),
+            render_threads: thread::available_parallelism().map_or(1, NonZeroUsize::get),
         }
     }
 
     pub fn clear(&mut self) {
-        self.color_buffer = Image::gen_image_color(self.width as i32, self.height as i32, self.background_color);
+        self.pixels.fill(rgba(self.background_color));
     }
 
     pub fn set_pixel(&mut self, x: u32, y: u32) {
-        if x < self.width && y < self.height {
-            self.color_buffer.draw_pixel(x as i32, y as i32, self.current_color);
-        }
+        self.set_pixel_with_color(x, y, self.current_color);
     }
 
     pub fn set_pixel_with_color(&mut self, x: u32, y: u32, color: Color) {
         if x < self.width && y < self.height {
-            self.color_buffer.draw_pixel(x as i32, y as i32, color);
+            self.pixels[(y * self.width + x) as usize] = rgba(color);
+        }
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> Color {
+        let [r, g, b, a] = self.pixels[(y * self.width + x) as usize];
+        Color::new(r, g, b, a)
+    }
+
+    pub fn pixels(&self) -> &[[u8; 4]] {
+        &self.pixels
+    }
+
+    /// Cambia el tamaño (ventana redimensionada o otra escala de render). La textura de GPU
+    /// se vuelve a crear en el siguiente swap.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![rgba(self.background_color); (width * height) as usize];
+        self.texture = None;
+    }
+
+    /// Copia `source` con su esquina superior izquierda en (x, y); lo que cae fuera se recorta.
+    /// La carrera a pantalla dividida dibuja cada vista aparte y la pega en su mitad.
+    pub fn blit(&mut self, source: &Framebuffer, x: u32, y: u32) {
+        let width = source.width.min(self.width.saturating_sub(x)) as usize;
+        for row in 0..source.height.min(self.height.saturating_sub(y)) {
+            let from = (row * source.width) as usize;
+            let to = ((y + row) * self.width + x) as usize;
+            self.pixels[to..to + width].copy_from_slice(&source.pixels[from..from + width]);
+        }
+    }
+
+    pub fn render_threads(&self) -> usize {
+        self.render_threads
+    }
+
+    // 0 is treated as 1
+    pub fn set_render_threads(&mut self, threads: usize) {
+        self.render_threads = threads.max(1);
+    }
+
+    /// Dibuja la imagen columna por columna, repartiendo bandas de columnas entre hilos.
+    ///
+    /// `render_column(x, column)` recibe la columna `x` de arriba abajo, ya rellena con
+    /// el color de fondo, y su resultado se devuelve en orden de columna. Cada banda es
+    /// un trozo contiguo de un búfer por columnas, así que los hilos nunca comparten
+    /// píxeles y el resultado es idéntico con cualquier número de hilos. La copia de
+    /// vuelta a filas también se reparte, por bandas de filas.
+    pub fn render_columns<T: Send>(
+        &mut self,
+        render_column: impl Fn(u32, &mut [[u8; 4]]) -> T + Sync,
+    ) -> Vec<T> {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        if width == 0 || height == 0 {
+            return Vec::new();
+        }
+
+        let mut columns = std::mem::take(&mut self.columns);
+        columns.clear();
+        columns.resize(width * height, rgba(self.background_color));
+
+        let render_band = |first: usize, band: &mut [[u8; 4]]| -> Vec<T> {
+            band.chunks_mut(height)
+                .enumerate()
+                .map(|(i, column)| render_column((first + i) as u32, column))
+                .collect()
+        };
+        let threads = self.render_threads.min(width);
+        let results = if threads <= 1 {
+            render_band(0, &mut columns)
+        } else {
+            let band_width = width.div_ceil(threads);
+            let render_band = &render_band;
+            thread::scope(|scope| {
+                let workers: Vec<_> = columns
+                    .chunks_mut(band_width * height)
+                    .enumerate()
+                    .map(|(band, slice)| scope.spawn(move || render_band(band * band_width, slice)))
+                    .collect();
+                workers
+                    .into_iter()
+                    .flat_map(|worker| worker.join().expect("Falló un hilo de render"))
+                    .collect()
+            })
+        };
+
+        // Back to the row-major layout the texture upload expects, one band of rows per thread
+        let copy_rows = |first: usize, band: &mut [[u8; 4]]| {
+            for (i, row) in band.chunks_mut(width).enumerate() {
+                let y = first + i;
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    *pixel = columns[x * height + y];
+                }
+            }
+        };
+        let threads = self.render_threads.min(height);
+        if threads <= 1 {
+            copy_rows(0, &mut self.pixels);
+        } else {
+            let band_height = height.div_ceil(threads);
+            let copy_rows = &copy_rows;
+            thread::scope(|scope| {
+                for (band, rows) in self.pixels.chunks_mut(band_height * width).enumerate() {
+                    scope.spawn(move || copy_rows(band * band_height, rows));
+                }
+            });
+        }
+        self.columns = columns;
+        results
+    }
+
+    // Copy this frame into the persistent GPU texture, creating it the first time
+    fn upload(&mut self, window: &mut RaylibHandle, raylib_thread: &RaylibThread) -> bool {
+        if self.texture.is_none() {
+            let image = Image::gen_image_color(self.width as i32, self.height as i32, Color::BLACK);
+            match window.load_texture_from_image(raylib_thread, &image) {
+                Ok(texture) => self.texture = Some(texture),
+                Err(e) => {
+                    println!("⚠️ No se pudo crear la textura del framebuffer: {}", e);
+                    return false;
+                }
+            }
+        }
+        match self.texture.as_mut().map(|texture| texture.update_texture(self.pixels.as_flattened())) {
+            Some(Ok(())) => true,
+            Some(Err(e)) => {
+                println!("⚠️ Error actualizando la textura del framebuffer: {}", e);
+                false
+            }
+            None => false,
         }
     }
 
@@ -45,31 +212,27 @@ impl Framebuffer {
         self.current_color = color;
     }
 
-    pub fn _render_to_file(&self, file_path: &str) {
-        self.color_buffer.export_image(file_path);
-    }
-
     pub fn swap_buffers(
-        &self,
+        &mut self,
         window: &mut RaylibHandle,
         raylib_thread: &RaylibThread,
     ) {
-        if let Ok(texture) = window.load_texture_from_image(raylib_thread, &self.color_buffer) {
+        if self.upload(window, raylib_thread) && let Some(texture) = &self.texture {
             let mut renderer = window.begin_drawing(raylib_thread);
             renderer.clear_background(Color::BLACK);
-            renderer.draw_texture(&texture, 0, 0, Color::WHITE);
+            renderer.draw_texture(texture, 0, 0, Color::WHITE);
         }
     }
 
     pub fn swap_buffers_with_fps(
-        &self,
+        &mut self,
         window: &mut RaylibHandle,
         raylib_thread: &RaylibThread,
         fps: f32,
     ) {
-        if let Ok(texture) = window.load_texture_from_image(raylib_thread, &self.color_buffer) {
+        if self.upload(window, raylib_thread) && let Some(texture) = &self.texture {
             let mut renderer = window.begin_drawing(raylib_thread);
-            renderer.draw_texture(&texture, 0, 0, Color::WHITE);
+            renderer.draw_texture(texture, 0, 0, Color::WHITE);
             
             // Draw FPS counter with color coding
             let fps_color = if fps >= 15.0 { 
@@ -98,18 +261,24 @@ impl Framebuffer {
     }
 
     pub fn swap_buffers_with_fps_and_minimap(
-        &self,
+        &mut self,
         window: &mut RaylibHandle,
         raylib_thread: &RaylibThread,
-        fps: f32,
         player: &Player,
         maze: &Vec<Vec<char>>,
-        current_track_info: Option<&str>,
-    ) {
-        if let Ok(texture) = window.load_texture_from_image(raylib_thread, &self.color_buffer) {
+        hud: &Hud,
+        capture_frame: bool,
+    ) -> Option<Image> {
+        let fps = hud.fps;
+        // HUD positions come from the window, not the framebuffer, which may be rendered smaller
+        let ui = Layout::of(window);
+        if self.upload(window, raylib_thread) && let Some(texture) = &self.texture {
             let mut renderer = window.begin_drawing(raylib_thread);
             renderer.clear_background(Color::BLACK);
-            renderer.draw_texture(&texture, 0, 0, Color::WHITE);
+            // Stretch the frame over the window (render scale < 1 upscales it)
+            let source = Rectangle::new(0.0, 0.0, self.width as f32, self.height as f32);
+            let screen = Rectangle::new(0.0, 0.0, ui.width as f32, ui.height as f32);
+            renderer.draw_texture_pro(texture, source, screen, Vector2::zero(), 0.0, Color::WHITE);
             
             // Draw FPS counter with color coding
             let fps_color = if fps >= 15.0 { 
@@ -120,7 +289,7 @@ impl Framebuffer {
                 Color::RED 
             };
             let fps_text = format!("FPS: {:.1}", fps);
-            renderer.draw_text(&fps_text, 10, 10, 20, fps_color);
+            renderer.draw_text(&fps_text, ui.px(10), ui.px(10), ui.font(20), fps_color);
             
             // Draw performance status
             let status = if fps >= 15.0 { 
@@ -130,30 +299,101 @@ impl Framebuffer {
             } else { 
                 "LOW" 
             };
-            renderer.draw_text(&format!("Performance: {}", status), 10, 35, 16, fps_color);
+            renderer.draw_text(&format!("Performance: {}", status), ui.px(10), ui.px(35), ui.font(16), fps_color);
             
             // Draw minimap in top-right corner
-            self.draw_minimap(&mut renderer, player, maze);
+            self.draw_minimap(&mut renderer, &ui, ui.width, player, maze, hud);
+
+            if !hud.breadcrumbs.is_empty() {
+                renderer.draw_text("💡 Pista activa: sigue las migas doradas", ui.px(10), ui.px(105), ui.font(16), Color::GOLD);
+            }
+
+            // Capture counter
+            if hud.total_pokemon > 0 {
+                let capture_color = if hud.captured == hud.total_pokemon { Color::GOLD } else { Color::WHITE };
+                let capture_text = format!("Pokémon: {}/{} capturados", hud.captured, hud.total_pokemon);
+                renderer.draw_text(&capture_text, ui.px(10), ui.px(130), ui.font(18), capture_color);
+            }
+            renderer.draw_text(&format!("Vidas: {}", hud.lives), ui.px(10), ui.px(152), ui.font(18), Color::new(255, 105, 180, 255));
+            draw_stamina(&mut renderer, &ui, ui.px(10), ui.px(176), player);
+            if hud.goal_locked {
+                let remaining = hud.total_pokemon - hud.captured;
+                let text = format!("¡Faltan {} Pokémon por capturar para salir!", remaining);
+                let text_width = renderer.measure_text(&text, ui.font(20));
+                renderer.draw_text(&text, (ui.width - text_width) / 2, ui.height / 2 - ui.px(60), ui.font(20), Color::ORANGE);
+            }
             
             // Draw Taylor Swift music info
-            if let Some(track_info) = current_track_info {
-                renderer.draw_text("🎵 Now Playing:", 10, 60, 16, Color::new(255, 192, 203, 255)); // Pink
-                renderer.draw_text(track_info, 10, 80, 14, Color::new(255, 105, 180, 255)); // Hot pink
+            if let Some(track_info) = hud.track_info {
+                renderer.draw_text("🎵 Now Playing:", ui.px(10), ui.px(60), ui.font(16), Color::new(255, 192, 203, 255)); // Pink
+                renderer.draw_text(track_info, ui.px(10), ui.px(80), ui.font(14), Color::new(255, 105, 180, 255)); // Hot pink
             }
             
             // Draw music controls
-            renderer.draw_text("N: Next Track | P: Previous | SPACE: Toggle Audio", 10, self.height as i32 - 50, 14, Color::new(200, 200, 200, 255));
+            renderer.draw_text("N: Next Track | P: Previous | SPACE: Toggle Audio", ui.px(10), ui.bottom(50), ui.font(14), Color::new(200, 200, 200, 255));
             
             // Draw controls info
-            renderer.draw_text("M: Toggle 2D/3D | WASD/Arrows: Move", 10, self.height as i32 - 30, 16, Color::WHITE);
+            renderer.draw_text("M: Toggle 2D/3D | WASD/Arrows: Move | H: Hint (+15s) | F11: Fullscreen", ui.px(10), ui.bottom(30), ui.font(16), Color::WHITE);
+
+            let frame = capture_frame.then(|| read_back(&mut renderer, raylib_thread));
+            if hud.recording {
+                renderer.draw_circle(ui.right(190), ui.px(20), ui.px(6) as f32, Color::RED);
+                renderer.draw_text("REC", ui.right(180), ui.px(12), ui.font(16), Color::RED);
+            }
+            return frame;
         }
+        None
     }
 
-    fn draw_minimap(&self, renderer: &mut RaylibDrawHandle, player: &Player, maze: &Vec<Vec<char>>) {
-        let minimap_width = 160;
-        let minimap_height = 120;
-        let minimap_x = self.width as i32 - minimap_width - 10; // 10 pixels from right edge
-        let minimap_y = 10; // 10 pixels from top
+    /// Carrera a pantalla dividida: el frame ya trae una vista por mitad y aquí se añade
+    /// el minimapa de cada jugador en la esquina de su mitad.
+    pub fn swap_buffers_split(
+        &mut self,
+        window: &mut RaylibHandle,
+        raylib_thread: &RaylibThread,
+        players: &[Player],
+        maze: &Vec<Vec<char>>,
+        hud: &Hud,
+        capture_frame: bool,
+    ) -> Option<Image> {
+        let ui = Layout::of(window);
+        if self.upload(window, raylib_thread) && let Some(texture) = &self.texture {
+            let mut renderer = window.begin_drawing(raylib_thread);
+            renderer.clear_background(Color::BLACK);
+            let source = Rectangle::new(0.0, 0.0, self.width as f32, self.height as f32);
+            let screen = Rectangle::new(0.0, 0.0, ui.width as f32, ui.height as f32);
+            renderer.draw_texture_pro(texture, source, screen, Vector2::zero(), 0.0, Color::WHITE);
+
+            let half = ui.width / players.len().max(1) as i32;
+            for (i, player) in players.iter().enumerate() {
+                let left = half * i as i32;
+                renderer.draw_text(&format!("JUGADOR {}", i + 1), left + ui.px(10), ui.px(10), ui.font(20), RACER_COLORS[i % RACER_COLORS.len()]);
+                draw_stamina(&mut renderer, &ui, left + ui.px(10), ui.px(60), player);
+                self.draw_minimap(&mut renderer, &ui, left + half, player, maze, hud);
+            }
+
+            renderer.draw_text(&format!("FPS: {:.1}", hud.fps), ui.px(10), ui.px(35), ui.font(16), Color::LIGHTGRAY);
+            if let Some(track_info) = hud.track_info {
+                renderer.draw_text(track_info, ui.px(10), ui.bottom(50), ui.font(14), Color::new(255, 105, 180, 255)); // Hot pink
+            }
+            renderer.draw_text(&format!("{} | Un gamepad por jugador", hud.key_hint), ui.px(10), ui.bottom(30), ui.font(16), Color::WHITE);
+
+            let frame = capture_frame.then(|| read_back(&mut renderer, raylib_thread));
+            if hud.recording {
+                renderer.draw_circle(ui.right(190), ui.bottom(20), ui.px(6) as f32, Color::RED);
+                renderer.draw_text("REC", ui.right(180), ui.bottom(28), ui.font(16), Color::RED);
+            }
+            return frame;
+        }
+        None
+    }
+
+    // `right` is the screen x the minimap hangs from: the window edge, or the edge of a split-screen half
+    fn draw_minimap(&self, renderer: &mut RaylibDrawHandle, ui: &Layout, right: i32, player: &Player, maze: &Vec<Vec<char>>, hud: &Hud) {
+        let minimap_width = ui.px(160);
+        let minimap_height = ui.px(120);
+        let minimap_x = right - ui.px(170); // 10 pixels from right edge
+        let minimap_y = ui.px(10); // 10 pixels from top
 
         let scale_x = minimap_width as f32 / maze[0].len() as f32;
         let scale_y = minimap_height as f32 / maze.len() as f32;
@@ -163,7 +403,7 @@ impl Framebuffer {
         renderer.draw_rectangle_lines(minimap_x, minimap_y, minimap_width, minimap_height, Color::YELLOW);
         
         // Draw minimap title
-        renderer.draw_text("MINIMAP", minimap_x + 5, minimap_y - 20, 14, Color::YELLOW);
+        renderer.draw_text("MINIMAP", minimap_x + ui.px(5), minimap_y - ui.px(20), ui.font(14), Color::YELLOW);
         
         // Draw the maze
         for (row_idx, row) in maze.iter().enumerate() {
@@ -174,7 +414,8 @@ impl Framebuffer {
                 let rect_h = scale_y.max(1.0) as i32;
                 
                 let color = match cell {
-                    '+' | '-' | '|' => Color::BROWN,
+                    GOAL_DOOR => Color::ORANGE,
+                    wall if is_wall_cell(wall) => Color::BROWN,
                     'g' => Color::GOLD,
                     's' => Color::LIME,
                     _ => Color::new(34, 139, 34, 100), // Green translucent for open spaces
@@ -186,21 +427,36 @@ impl Framebuffer {
             }
         }
         
+        // Hint trail
+        for &(col_idx, row_idx) in hud.breadcrumbs {
+            let crumb_x = minimap_x + ((col_idx as f32 + 0.5) * scale_x) as i32;
+            let crumb_y = minimap_y + ((row_idx as f32 + 0.5) * scale_y) as i32;
+            renderer.draw_circle(crumb_x, crumb_y, 2.0 * ui.scale, Color::GOLD);
+        }
+
         // Draw player position (using correct world block size)
-        let world_block_size = 20.0; // Must match the block size used in player.rs and rendering
+        let world_block_size = WORLD_BLOCK_SIZE as f32;
+
+        // Enemies
+        for npc in hud.npcs {
+            let npc_x = minimap_x + (npc.position.x / world_block_size * scale_x) as i32;
+            let npc_y = minimap_y + (npc.position.y / world_block_size * scale_y) as i32;
+            renderer.draw_circle(npc_x, npc_y, 3.0 * ui.scale, Color::PURPLE);
+        }
+
         let player_x = minimap_x + (player.pos.x / world_block_size * scale_x) as i32;
         let player_y = minimap_y + (player.pos.y / world_block_size * scale_y) as i32;
-        renderer.draw_circle(player_x, player_y, 4.0, Color::RED);
+        renderer.draw_circle(player_x, player_y, 4.0 * ui.scale, Color::RED);
         
         // Draw player direction indicator
-        let dir_length = 12.0;
+        let dir_length = 12.0 * ui.scale;
         let end_x = player_x + (player.a.cos() * dir_length) as i32;
         let end_y = player_y + (player.a.sin() * dir_length) as i32;
         renderer.draw_line(player_x, player_y, end_x, end_y, Color::YELLOW);
 
         // Draw player coordinates for debugging
         let coord_text = format!("({:.0}, {:.0})", player.pos.x / world_block_size, player.pos.y / world_block_size);
-        renderer.draw_text(&coord_text, minimap_x, minimap_y + minimap_height + 5, 12, Color::WHITE);
+        renderer.draw_text(&coord_text, minimap_x, minimap_y + minimap_height + ui.px(5), ui.font(12), Color::WHITE);
     }
 
     // Simplified draw method for game states that don't need framebuffer rendering
@@ -245,7 +501,7 @@ impl Framebuffer {
         let minimap_height = maze.len() * minimap_size;
         let minimap_x = self.width as i32 - minimap_width as i32 - 10;
         let minimap_y = 60;
-        let world_block_size = 20.0;
+        let world_block_size = WORLD_BLOCK_SIZE as f32;
 
         // Draw minimap background
         d.draw_rectangle(minimap_x - 2, minimap_y - 2, minimap_width as i32 + 4, minimap_height as i32 + 4, Color::BLACK);
@@ -257,7 +513,8 @@ impl Framebuffer {
                 let y = minimap_y + (row_index * minimap_size) as i32;
 
                 let color = match cell {
-                    '+' | '-' | '|' => Color::BROWN,    // Walls
+                    GOAL_DOOR => Color::ORANGE,          // Exit door
+                    wall if is_wall_cell(wall) => Color::BROWN, // Walls
                     'g' => Color::GOLD,                  // Goal
                     's' => Color::LIME,                  // Start
                     _ => Color::new(200, 200, 200, 255), // Empty space
@@ -284,4 +541,101 @@ impl Framebuffer {
         let coord_text = format!("({:.0}, {:.0})", player.pos.x / world_block_size, player.pos.y / world_block_size);
         d.draw_text(&coord_text, minimap_x, minimap_y + minimap_height as i32 + 5, 12, Color::WHITE);
     }
-}
\ No newline at end of file
+}
+// Sprint meter; orange while it refills after running out
+fn draw_stamina(renderer: &mut RaylibDrawHandle, ui: &Layout, x: i32, y: i32, player: &Player) {
+    let (width, height) = (ui.px(120), ui.px(8));
+    let color = if player.can_sprint() { Color::SKYBLUE } else { Color::ORANGE };
+    renderer.draw_rectangle(x, y, width, height, Color::new(0, 0, 0, 150));
+    renderer.draw_rectangle(x, y, (width as f32 * player.stamina) as i32, height, color);
+    renderer.draw_rectangle_lines(x, y, width, height, Color::WHITE);
+}
+
+// Screenshots and recorded frames: the whole composed screen, HUD included
+// The finished frame with HUD and minimap, for F12 and F9; `Capture::save` encodes it elsewhere
+fn read_back(renderer: &mut RaylibDrawHandle, raylib_thread: &RaylibThread) -> Image {
+    // Text is batched; flush it so the read-back sees it
+    unsafe { raylib::ffi::rlDrawRenderBatchActive() };
+    renderer.load_image_from_screen(raylib_thread)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixels_are_row_major_rgba() {
+        let mut framebuffer = Framebuffer::new(4, 3);
+        framebuffer.set_pixel_with_color(1, 2, Color::new(10, 20, 30, 255));
+        assert_eq!(framebuffer.pixels()[2 * 4 + 1], [10, 20, 30, 255]);
+        assert_eq!(framebuffer.pixels().as_flattened().len(), 4 * 3 * 4);
+
+        // Out of bounds is ignored instead of wrapping to the next row
+        framebuffer.set_pixel_with_color(4, 0, Color::RED);
+        assert_eq!(framebuffer.pixels()[4], [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn clear_fills_with_background() {
+        let mut framebuffer = Framebuffer::new(2, 2);
+        framebuffer.set_background_color(Color::new(50, 50, 100, 255));
+        framebuffer.set_current_color(Color::WHITE);
+        framebuffer.set_pixel(0, 0);
+        framebuffer.clear();
+        assert!(framebuffer.pixels().iter().all(|&p| p == [50, 50, 100, 255]));
+    }
+
+    #[test]
+    fn resize_reallocates_pixels() {
+        let mut framebuffer = Framebuffer::new(4, 3);
+        framebuffer.set_background_color(Color::new(50, 50, 100, 255));
+        framebuffer.resize(6, 2);
+        assert_eq!((framebuffer.width, framebuffer.height), (6, 2));
+        assert_eq!(framebuffer.pixels().len(), 12);
+        assert!(framebuffer.pixels().iter().all(|&p| p == [50, 50, 100, 255]));
+    }
+
+    #[test]
+    fn blit_copies_into_place_and_clips() {
+        let mut framebuffer = Framebuffer::new(5, 3);
+        let mut view = Framebuffer::new(3, 2);
+        view.set_background_color(Color::new(9, 9, 9, 255));
+        view.clear();
+        view.set_pixel_with_color(0, 1, Color::RED);
+
+        framebuffer.blit(&view, 1, 0);
+        assert_eq!(framebuffer.pixels()[0], [0, 0, 0, 255]);
+        assert_eq!(framebuffer.pixels()[1], [9, 9, 9, 255]);
+        assert_eq!(framebuffer.pixels()[5 + 1], [255, 0, 0, 255]);
+        assert_eq!(framebuffer.pixels()[2 * 5 + 1], [0, 0, 0, 255]); // Below the view
+
+        // Only the first two columns fit
+        framebuffer.blit(&view, 3, 2);
+        assert_eq!(framebuffer.pixels()[2 * 5 + 3], [9, 9, 9, 255]);
+        assert_eq!(framebuffer.pixels()[2 * 5 + 4], [9, 9, 9, 255]);
+        framebuffer.blit(&view, 5, 0);
+    }
+
+    #[test]
+    fn column_bands_match_single_thread() {
+        let render = |threads: usize| {
+            let mut framebuffer = Framebuffer::new(37, 5);
+            framebuffer.set_render_threads(threads);
+            let tops = framebuffer.render_columns(|x, column| {
+                let top = x as usize % column.len();
+                for (y, pixel) in column.iter_mut().enumerate().skip(top) {
+                    *pixel = [x as u8, y as u8, 7, 255];
+                }
+                top
+            });
+            (tops, framebuffer.pixels().to_vec())
+        };
+        let single = render(1);
+        assert_eq!(single.1[5], [5, 0, 7, 255]);
+        assert_eq!(single.1[1], [0, 0, 0, 255]); // Column 1 starts at row 1
+        assert_eq!(single.1[37 + 1], [1, 1, 7, 255]);
+        for threads in [2, 3, 8, 64] {
+            assert_eq!(render(threads), single, "{} hilos", threads);
+        }
+    }
+}
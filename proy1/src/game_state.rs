@@ -1,5 +1,17 @@
 use raylib::prelude::*;
-use gilrs::{Gilrs, Button, Event, EventType};
+use crate::maze::{Maze, MazeAlgorithm};
+use crate::maze_file;
+use crate::hints::HINT_PENALTY;
+use crate::textures::SurfaceStyle;
+use crate::framebuffer::RACER_COLORS;
+use crate::gamepad::Gamepads;
+use gilrs::Button;
+use crate::input::{pressed_binding, Action, Binding, Bindings, Input};
+use crate::race::RACERS;
+use crate::settings::{Setting, Settings};
+use crate::ui::Layout;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum GameState {
@@ -7,6 +19,9 @@ pub enum GameState {
     LevelSelect, // Selección de niveles temáticos
     Playing,
     Victory,     // Pantalla de éxito al completar nivel
+    Settings,    // Sensibilidad, velocidades, FOV, zona muerta y volumen
+    Controls,    // Teclas y botones de cada acción
+    Race,        // Carrera a pantalla dividida para dos jugadores
 }
 
 pub struct GameStateManager {
@@ -14,20 +29,112 @@ pub struct GameStateManager {
     pub selected_level: usize,
     pub selected_menu_option: usize,
     pub menu_options: Vec<String>,
+    pub seed_input: String,        // Semilla tecleada en la selección de región (vacía = aleatoria)
+    pub current_seed: Option<u64>, // Semilla del laberinto en juego
+    pub custom_levels: Vec<Maze>,  // Niveles diseñados a mano en levels/, después de las tres regiones
+    pub level_time: Option<Duration>, // Tiempo final del último nivel, con la penalización de pistas
+    pub hints_used: u32,
+    pub pokemon_captured: (usize, usize), // Capturados / colocados en el último nivel
+    pub settings: Settings,               // Se guardan en settings.json al salir de Ajustes
+    pub selected_setting: usize,
+    pub selected_binding: usize,          // Fila elegida en Controles (ver `binding_row`); la última es "Guardar y volver"
+    pub capturing_binding: Option<Instant>, // Desde cuándo se espera la tecla o botón nuevo para esa acción
+    pub quit_requested: bool,             // "Salir" en el menú principal
+    pub race_winner: Option<usize>,       // Ganador de la última carrera; None si la victoria fue en solitario
+    pub algorithm_override: Option<MazeAlgorithm>, // Elegido en la selección de región; None = el de la región
+}
+
+// u64::MAX has 20 digits
+const MAX_SEED_DIGITS: usize = 20;
+// Generated regions listed before the levels/ directory
+const BUILTIN_LEVELS: usize = 3;
+// Level cards that fit on the level-select screen at once
+const VISIBLE_LEVELS: usize = 3;
+// Action rows that fit on the controls screen at once
+const VISIBLE_BINDINGS: usize = 8;
+// Waiting for a new control gives up after this long
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(5);
+// Every action, then the movement keys of each race player
+const BINDING_ROWS: usize = Action::ALL.len() + RACERS * Action::MOVEMENT.len();
+
+/// Qué edita una fila de la pantalla de Controles: una acción de los controles generales
+/// (`None`) o de la mitad del teclado de un jugador de la carrera (`Some(jugador)`).
+fn binding_row(row: usize) -> Option<(Option<usize>, Action)> {
+    if let Some(&action) = Action::ALL.get(row) {
+        return Some((None, action));
+    }
+    let row = row - Action::ALL.len();
+    let racer = row / Action::MOVEMENT.len();
+    (racer < RACERS).then(|| (Some(racer), Action::MOVEMENT[row % Action::MOVEMENT.len()]))
+}
+
+fn binding_row_label(racer: Option<usize>, action: Action) -> String {
+    match racer {
+        Some(racer) => format!("Carrera J{}: {}", racer + 1, action.label()),
+        None => action.label().to_string(),
+    }
 }
 
 impl GameStateManager {
     pub fn new() -> Self {
-        GameStateManager {
+        let mut manager = GameStateManager {
             current_state: GameState::Welcome,
             selected_level: 0,
             selected_menu_option: 0,
             menu_options: vec![
                 "Iniciar Aventura".to_string(),
                 "Seleccionar Región".to_string(),
+                "Carrera 2 Jugadores".to_string(),
+                "Ajustes".to_string(),
+                "Controles".to_string(),
                 "Salir".to_string(),
             ],
+            seed_input: String::new(),
+            current_seed: None,
+            custom_levels: Vec::new(),
+            level_time: None,
+            hints_used: 0,
+            pokemon_captured: (0, 0),
+            settings: Settings::default(),
+            selected_setting: 0,
+            selected_binding: 0,
+            capturing_binding: None,
+            quit_requested: false,
+            race_winner: None,
+            algorithm_override: None,
+        };
+        manager.load_custom_levels();
+        manager
+    }
+
+    // Volver a leer levels/; los archivos inválidos se omiten con un aviso
+    pub fn load_custom_levels(&mut self) {
+        let dir = maze_file::levels_dir();
+        self.custom_levels = maze_file::list_levels(&dir)
+            .into_iter()
+            .filter_map(|path| match Maze::load(&path) {
+                Ok(maze) => Some(maze),
+                Err(e) => {
+                    println!("⚠️ Nivel {:?} ignorado: {}", path, e);
+                    None
+                }
+            })
+            .collect();
+        if !self.custom_levels.is_empty() {
+            println!("📁 {} nivel(es) cargados desde {:?}", self.custom_levels.len(), dir);
         }
+        self.selected_level = self.selected_level.min(self.level_count() - 1);
+    }
+
+    pub fn level_count(&self) -> usize {
+        BUILTIN_LEVELS + self.custom_levels.len()
+    }
+
+    // Nivel de archivo elegido, si la selección está más allá de las regiones generadas
+    pub fn selected_custom_level(&self) -> Option<&Maze> {
+        self.selected_level
+            .checked_sub(BUILTIN_LEVELS)
+            .and_then(|i| self.custom_levels.get(i))
     }
 
     // Obtener el tamaño del laberinto según el nivel seleccionado
@@ -40,8 +147,86 @@ impl GameStateManager {
         }
     }
 
+    // Algoritmo de generación según la región: cada uno da pasillos con distinta personalidad.
+    // El jugador puede cambiarlo por cualquier otro en la selección de región.
+    pub fn get_maze_algorithm(&self) -> MazeAlgorithm {
+        self.algorithm_override.unwrap_or_else(|| self.region_algorithm())
+    }
+
+    fn region_algorithm(&self) -> MazeAlgorithm {
+        match self.selected_level {
+            0 => MazeAlgorithm::BinaryTree,           // Fácil: pasillos rectos y predecibles
+            1 => MazeAlgorithm::Prim,                 // Medio: muchos callejones cortos
+            2 => MazeAlgorithm::RecursiveBacktracker, // Difícil: pasillos largos y sinuosos
+            _ => MazeAlgorithm::Prim,
+        }
+    }
+
+    // El de la región, luego cada uno de MazeAlgorithm::ALL, y otra vez el de la región
+    fn cycle_algorithm(&mut self) {
+        let next = match self.algorithm_override {
+            None => Some(0),
+            Some(current) => MazeAlgorithm::ALL.iter().position(|&a| a == current).map(|i| i + 1),
+        };
+        self.algorithm_override = next.and_then(|i| MazeAlgorithm::ALL.get(i).copied());
+    }
+
+    // Semilla escrita por el jugador; se consume al generar el siguiente laberinto
+    pub fn take_requested_seed(&mut self) -> Option<u64> {
+        let seed = self.seed_input.parse::<u64>().ok();
+        self.seed_input.clear();
+        seed
+    }
+
+    // Piso y techo de cada región (None = cielo abierto)
+    pub fn get_surface_style(&self) -> SurfaceStyle {
+        let (floor, ceiling) = match self.selected_level {
+            0 => ("wall3.jpg", None),                     // Centro Pokémon: pasto al aire libre
+            1 => ("wall4.jpg", Some("wall5.jpg")),        // Cueva Oscura: roca encima
+            2 => ("wall5.jpg", Some("wall1.jpg")),        // Torre Victoria: interior con techo
+            _ => ("wall3.jpg", None),
+        };
+        let region = SurfaceStyle { floor: floor.to_string(), ceiling: ceiling.map(str::to_string) };
+        match self.selected_custom_level() {
+            Some(level) => level.meta.surface_style(region),
+            None => region,
+        }
+    }
+
+    // Textura de cada carácter de pared en la región; el nivel propio tiene la última palabra
+    pub fn get_wall_textures(&self) -> BTreeMap<char, String> {
+        let region: &[(char, &str)] = match self.selected_level {
+            1 => &[('+', "mossy"), ('-', "wall4.jpg"), ('|', "wall2.jpg")], // Cueva Oscura: pilares con musgo
+            2 => &[('+', "brick"), ('-', "wall5.jpg"), ('|', "wall3.jpg")], // Torre Victoria: ladrillo
+            _ => &[],                                                        // Texturas por defecto
+        };
+        let mut textures: BTreeMap<char, String> =
+            region.iter().map(|&(wall, name)| (wall, name.to_string())).collect();
+        if let Some(level) = self.selected_custom_level() {
+            textures.extend(level.meta.wall_textures.clone());
+        }
+        textures
+    }
+
+    // Enemigos que patrullan cada región; los niveles propios pueden fijar otro número
+    pub fn get_enemy_count(&self) -> usize {
+        let region = match self.selected_level {
+            0 => 1, // Centro Pokémon: un solo Gastly despistado
+            1 => 2, // Cueva Oscura
+            2 => 3, // Torre Victoria
+            _ => 1,
+        };
+        self.selected_custom_level()
+            .and_then(|level| level.meta.enemies)
+            .unwrap_or(region)
+    }
+
     // Obtener información del nivel actual
     pub fn get_level_info(&self) -> (&str, &str, &str) {
+        if let Some(level) = self.selected_custom_level() {
+            let name = level.meta.name.as_deref().unwrap_or("Nivel personalizado");
+            return (name, "Nivel diseñado a mano", "📁 PERSONALIZADO");
+        }
         match self.selected_level {
             0 => ("Centro Pokémon", "Un laberinto básico para entrenadores novatos", "⭐ FÁCIL"),
             1 => ("Cueva Oscura", "Laberinto medio con obstáculos adicionales", "⭐⭐ MEDIO"),
@@ -50,233 +235,346 @@ impl GameStateManager {
         }
     }
 
-    pub fn update(&mut self, rl: &mut RaylibHandle, gilrs: &mut Gilrs) {
+    // Gamepad buttons arrive through `input`; the controls screen also needs the raw pads to bind them
+    pub fn update(&mut self, rl: &mut RaylibHandle, gamepads: &Gamepads, input: &Input) {
         match self.current_state {
-            GameState::Welcome => self.update_welcome(rl, gilrs),
-            GameState::LevelSelect => self.update_level_select(rl, gilrs),
-            GameState::Victory => self.update_victory(rl, gilrs),
+            GameState::Welcome => self.update_welcome(rl, gamepads, input),
+            GameState::LevelSelect => self.update_level_select(rl, input),
+            GameState::Victory => self.update_victory(input),
+            GameState::Settings => self.update_settings(input),
+            GameState::Controls => self.update_controls(rl, gamepads, input),
             _ => {}
         }
     }
 
-    fn update_welcome(&mut self, rl: &mut RaylibHandle, gilrs: &mut Gilrs) {
-        // Procesar eventos de gamepad
-        while let Some(Event { id, event, time: _ }) = gilrs.next_event() {
-            match event {
-                EventType::ButtonPressed(button, _) => {
-                    match button {
-                        Button::South => { // X en PS4 (Cross) - ACCEPT
-                            self.current_state = GameState::LevelSelect;
-                            return;
-                        },
-                        Button::Start => { // Options en PS4 - MENU
-                            self.current_state = GameState::LevelSelect;
-                            return;
-                        },
-                        _ => {}
-                    }
-                }
-                EventType::Connected => {
-                    println!("🎮 Controller connected: {}", gilrs.gamepad(id).name());
-                }
-                EventType::Disconnected => {
-                    println!("🎮 Controller disconnected");
-                }
-                _ => {}
-            }
+    fn update_welcome(&mut self, rl: &RaylibHandle, gamepads: &Gamepads, input: &Input) {
+        if input.pressed(Action::MenuUp) {
+            self.move_menu_selection(-1);
         }
-        
-        // Verificar estado actual de botones - REMOVIDO para evitar doble activación
-        for (_id, gamepad) in gilrs.gamepads() {
-            // Los botones se procesan solo en eventos ButtonPressed arriba
+        if input.pressed(Action::MenuDown) {
+            self.move_menu_selection(1);
         }
-        
-        // Controles de teclado
-        if rl.is_key_pressed(KeyboardKey::KEY_ENTER) || rl.is_key_pressed(KeyboardKey::KEY_SPACE) {
+        // Fixed shortcuts of this screen only: SPACE also accepts and Start goes straight to the regions
+        if input.pressed(Action::Accept) || rl.is_key_pressed(KeyboardKey::KEY_SPACE) {
+            self.activate_menu_option();
+        } else if gamepads.pressed(Button::Start) {
             self.current_state = GameState::LevelSelect;
         }
     }
 
-    fn update_level_select(&mut self, rl: &mut RaylibHandle, gilrs: &mut Gilrs) {
-        // Procesar eventos de gamepad
-        while let Some(Event { id: _, event, time: _ }) = gilrs.next_event() {
-            match event {
-                EventType::ButtonPressed(button, _) => {
-                    match button {
-                        Button::DPadLeft => {
-                            if self.selected_level > 0 {
-                                self.selected_level -= 1;
-                                println!("🎮 Level: {}", self.selected_level);
-                            }
-                        },
-                        Button::DPadRight => {
-                            if self.selected_level < 2 {
-                                self.selected_level += 1;
-                                println!("🎮 Level: {}", self.selected_level);
-                            }
-                        },
-                        Button::DPadUp => {
-                            if self.selected_level > 0 {
-                                self.selected_level -= 1;
-                                println!("🎮 Level up: {}", self.selected_level);
-                            }
-                        },
-                        Button::DPadDown => {
-                            if self.selected_level < 2 {
-                                self.selected_level += 1;
-                                println!("🎮 Level down: {}", self.selected_level);
-                            }
-                        },
-                        Button::South => { // X en PS4 - ACCEPT
-                            self.current_state = GameState::Playing;
-                            println!("🎮 Starting game");
-                            return;
-                        },
-                        Button::East => { // Circle en PS4 - BACK
-                            self.current_state = GameState::Welcome;
-                            println!("🎮 Back to menu");
-                            return;
-                        },
-                        _ => {}
-                    }
+    // Moverse por menu_options sin salirse de la lista
+    fn move_menu_selection(&mut self, delta: i32) {
+        let last = self.menu_options.len() as i32 - 1;
+        self.selected_menu_option = (self.selected_menu_option as i32 + delta).clamp(0, last) as usize;
+    }
+
+    fn activate_menu_option(&mut self) {
+        match self.menu_options[self.selected_menu_option].as_str() {
+            "Iniciar Aventura" => self.current_state = GameState::Playing,
+            "Seleccionar Región" => self.current_state = GameState::LevelSelect,
+            "Carrera 2 Jugadores" => self.current_state = GameState::Race,
+            "Ajustes" => self.current_state = GameState::Settings,
+            "Controles" => self.current_state = GameState::Controls,
+            "Salir" => self.quit_requested = true,
+            _ => {}
+        }
+    }
+
+    fn update_settings(&mut self, input: &Input) {
+        if input.pressed(Action::MenuUp) {
+            self.move_setting_selection(-1);
+        }
+        if input.pressed(Action::MenuDown) {
+            self.move_setting_selection(1);
+        }
+        let steps = input.pressed(Action::MenuRight) as i32 - input.pressed(Action::MenuLeft) as i32;
+        if steps != 0 {
+            self.settings.adjust(Setting::ALL[self.selected_setting], steps);
+        }
+        if input.pressed(Action::Restart) {
+            self.settings.reset_gameplay();
+        }
+        // ENTER or ESC (Accept or Back) save and return to the menu
+        if input.pressed(Action::Accept) || input.pressed(Action::Back) {
+            self.save_settings();
+            self.current_state = GameState::Welcome;
+        }
+    }
+
+    fn save_settings(&self) {
+        match self.settings.save() {
+            Ok(path) => println!("💾 Ajustes guardados en {}", path.display()),
+            Err(e) => println!("⚠️ Error guardando ajustes: {}", e),
+        }
+    }
+
+    fn move_setting_selection(&mut self, delta: i32) {
+        let last = Setting::ALL.len() as i32 - 1;
+        self.selected_setting = (self.selected_setting as i32 + delta).clamp(0, last) as usize;
+    }
+
+    fn row_bindings(&self, racer: Option<usize>) -> &Bindings {
+        racer.map_or(&self.settings.bindings, |racer| &self.settings.race_keys[racer])
+    }
+
+    fn row_bindings_mut(&mut self, racer: Option<usize>) -> &mut Bindings {
+        match racer {
+            Some(racer) => &mut self.settings.race_keys[racer],
+            None => &mut self.settings.bindings,
+        }
+    }
+
+    // Una fila por acción, las de movimiento de cada jugador de la carrera y al final "Guardar y volver"
+    fn update_controls(&mut self, rl: &mut RaylibHandle, gamepads: &Gamepads, input: &Input) {
+        let row = binding_row(self.selected_binding);
+
+        // Waiting for the new control: the first key, mouse button or gamepad input is taken.
+        // Back (checked first, so it can't be captured) or waiting too long cancel instead.
+        if let (Some(started), Some((racer, action))) = (self.capturing_binding, row) {
+            let label = binding_row_label(racer, action);
+            if input.pressed(Action::Back) || started.elapsed() >= CAPTURE_TIMEOUT {
+                self.capturing_binding = None;
+                println!("🎮 {}: sin cambios", label);
+            } else if let Some(binding) = pressed_binding(rl, gamepads) {
+                // A race player's pad is shared with everyone's, so their rows only take keys and mouse buttons
+                if racer.is_some() && binding.is_pad() {
+                    return;
                 }
-                _ => {}
+                self.row_bindings_mut(racer).add(action, binding);
+                self.capturing_binding = None;
+                println!("🎮 {} → {}", label, binding.name());
             }
+            return;
         }
-        
-        // Verificar estado actual de botones solo para navegación continua
-        for (_id, gamepad) in gilrs.gamepads() {
-            // REMOVIDO: No verificar South/East aquí para evitar doble activación
-            // Solo se procesan en los eventos ButtonPressed arriba
+
+        if input.pressed(Action::MenuUp) {
+            self.selected_binding = self.selected_binding.saturating_sub(1);
         }
-        
-        // Controles de teclado
-        if rl.is_key_pressed(KeyboardKey::KEY_LEFT) && self.selected_level > 0 {
-            self.selected_level -= 1;
+        if input.pressed(Action::MenuDown) {
+            self.selected_binding = (self.selected_binding + 1).min(BINDING_ROWS);
         }
-        if rl.is_key_pressed(KeyboardKey::KEY_RIGHT) && self.selected_level < 2 {
-            self.selected_level += 1;
+        if input.pressed(Action::MenuLeft) && let Some((racer, action)) = row {
+            self.row_bindings_mut(racer).remove_last(action);
+        }
+        if input.pressed(Action::Restart) {
+            self.settings.bindings = Bindings::default();
+            self.settings.race_keys = std::array::from_fn(Bindings::racer_keys);
         }
-        if rl.is_key_pressed(KeyboardKey::KEY_UP) && self.selected_level > 0 {
+        let back_selected = row.is_none();
+        if input.pressed(Action::Accept) && !back_selected {
+            self.capturing_binding = Some(Instant::now());
+        } else if (input.pressed(Action::Accept) && back_selected) || input.pressed(Action::Back) {
+            self.save_settings();
+            self.current_state = GameState::Welcome;
+        }
+    }
+
+    fn update_level_select(&mut self, rl: &mut RaylibHandle, input: &Input) {
+        if (input.pressed(Action::MenuLeft) || input.pressed(Action::MenuUp)) && self.selected_level > 0 {
             self.selected_level -= 1;
         }
-        if rl.is_key_pressed(KeyboardKey::KEY_DOWN) && self.selected_level < 2 {
+        if (input.pressed(Action::MenuRight) || input.pressed(Action::MenuDown)) && self.selected_level + 1 < self.level_count() {
             self.selected_level += 1;
         }
 
-        if rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+        // Entrada de semilla: dígitos para escribir, BACKSPACE para borrar
+        while let Some(c) = rl.get_char_pressed() {
+            if c.is_ascii_digit() && self.seed_input.len() < MAX_SEED_DIGITS {
+                self.seed_input.push(c);
+                // Descartar el dígito si la semilla ya no cabe en un u64
+                if self.seed_input.parse::<u64>().is_err() {
+                    self.seed_input.pop();
+                }
+            }
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+            self.seed_input.pop();
+        }
+
+        // Hand-made levels bring their own grid, so only generated regions use it
+        if input.pressed(Action::CycleAlgorithm) && self.selected_custom_level().is_none() {
+            self.cycle_algorithm();
+        }
+
+        if input.pressed(Action::Accept) {
             self.current_state = GameState::Playing;
         }
 
-        if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+        if input.pressed(Action::Back) {
             self.current_state = GameState::Welcome;
         }
     }
 
-    fn update_victory(&mut self, rl: &mut RaylibHandle, gilrs: &mut Gilrs) {
-        // Procesar eventos de gamepad
-        while let Some(Event { id: _, event, time: _ }) = gilrs.next_event() {
-            match event {
-                EventType::ButtonPressed(button, _) => {
-                    match button {
-                        Button::East => { // Circle en PS4 - BACK TO MENU
-                            self.current_state = GameState::Welcome;
-                            return;
-                        },
-                        Button::South => { // X en PS4 - RESTART LEVEL
-                            self.current_state = GameState::Playing;
-                            return;
-                        },
-                        Button::North => { // Triangle en PS4 - RESTART LEVEL
-                            self.current_state = GameState::Playing;
-                            return;
-                        },
-                        Button::Select => { // Share en PS4 - BACK TO MENU
-                            self.current_state = GameState::Welcome;
-                            return;
-                        },
-                        _ => {}
-                    }
-                }
-                _ => {}
-            }
-        }
-        
-        // Verificar estado actual de botones - REMOVIDO para evitar doble activación
-        for (_id, gamepad) in gilrs.gamepads() {
-            // Los botones se procesan solo en eventos ButtonPressed arriba
-        }
-        
-        // Controles de teclado
-        if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+    fn update_victory(&mut self, input: &Input) {
+        if input.pressed(Action::Back) {
             self.current_state = GameState::Welcome;
         }
-        if rl.is_key_pressed(KeyboardKey::KEY_R) {
-            self.current_state = GameState::Playing;
+        if input.pressed(Action::Accept) || input.pressed(Action::Restart) {
+            self.current_state = self.replay_state();
         }
     }
 
+    // Jugar de nuevo desde Victoria: otra carrera si la última lo fue
+    pub fn replay_state(&self) -> GameState {
+        if self.race_winner.is_some() { GameState::Race } else { GameState::Playing }
+    }
+
     pub fn draw_welcome(&self, d: &mut RaylibDrawHandle) {
-        self.draw_background(d, Color::new(25, 42, 86, 255));
-        self.draw_title(d, "POKÉMON RAYCASTER", 202, 152, 48, Color::BLACK, Color::new(255, 204, 51, 255));
+        let ui = Layout::of(d);
+        let mut d = d.begin_mode2D(ui.camera());
+        self.draw_background(&mut d, &ui, Color::new(25, 42, 86, 255));
+        self.draw_title(&mut d, "POKÉMON RAYCASTER", 202, 152, 48, Color::BLACK, Color::new(255, 204, 51, 255));
         d.draw_text("Aventura en el Laberinto", 240, 210, 24, Color::WHITE);
-        d.draw_text("¡Bienvenido, joven entrenador!", 220, 280, 24, Color::new(255, 204, 51, 255));
-        d.draw_text("Tu aventura en el mundo Pokémon está a punto de comenzar.", 120, 320, 18, Color::WHITE);
-        d.draw_text("Explora laberintos místicos y encuentra todos los Pokémon.", 130, 350, 18, Color::WHITE);
+        d.draw_text("¡Bienvenido, joven entrenador!", 220, 250, 24, Color::new(255, 204, 51, 255));
+        d.draw_text("Tu aventura en el mundo Pokémon está a punto de comenzar.", 120, 282, 18, Color::WHITE);
+        d.draw_text("Explora laberintos místicos y encuentra todos los Pokémon.", 130, 304, 18, Color::WHITE);
+
+        self.draw_menu_options(&mut d);
+        self.draw_controls(&mut d);
+        self.draw_pokeball_decorations(&mut d);
+        d.draw_text("↑ ↓ para elegir, ENTER para confirmar", 215, 540, 20, Color::new(255, 204, 51, 255));
+    }
 
-        self.draw_controls(d);
-        self.draw_pokeball_decorations(d);
-        d.draw_text("Presiona ESPACIO o ENTER para comenzar", 180, 540, 20, Color::new(255, 204, 51, 255));
+    pub fn draw_settings(&self, d: &mut RaylibDrawHandle) {
+        let ui = Layout::of(d);
+        let mut d = d.begin_mode2D(ui.camera());
+        self.draw_background(&mut d, &ui, Color::new(25, 42, 86, 255));
+        self.draw_title(&mut d, "AJUSTES", 322, 102, 40, Color::BLACK, Color::new(255, 204, 51, 255));
+
+        for (i, &setting) in Setting::ALL.iter().enumerate() {
+            let y = 160 + i as i32 * 32;
+            let is_selected = i == self.selected_setting;
+            if is_selected {
+                d.draw_rectangle(120, y - 6, 560, 30, Color::new(255, 204, 51, 100));
+                d.draw_rectangle_lines_ex(Rectangle::new(120.0, (y - 6) as f32, 560.0, 30.0), 2.0, Color::new(255, 204, 51, 255));
+            }
+            let text_color = if is_selected { Color::new(255, 204, 51, 255) } else { Color::WHITE };
+            let prefix = if is_selected { "► " } else { "  " };
+            d.draw_text(&format!("{}{}", prefix, setting.label()), 140, y, 20, text_color);
+            let value = if is_selected {
+                format!("◄ {} ►", self.settings.display(setting))
+            } else {
+                self.settings.display(setting)
+            };
+            d.draw_text(&value, 500, y, 20, text_color);
+        }
+
+        d.draw_rectangle(100, 490, 600, 90, Color::new(50, 50, 50, 200));
+        d.draw_text("↑ ↓ para elegir, ← → para cambiar el valor", 160, 505, 18, Color::WHITE);
+        d.draw_text("R: valores por defecto", 290, 530, 16, Color::LIGHTGRAY);
+        d.draw_text("ENTER o ESC para guardar y volver", 235, 552, 16, Color::LIGHTGRAY);
     }
 
     pub fn draw_level_select(&self, d: &mut RaylibDrawHandle) {
-        self.draw_background(d, Color::new(25, 42, 86, 255));
-        self.draw_title(d, "SELECCIONAR REGIÓN", 252, 102, 32, Color::BLACK, Color::new(255, 204, 51, 255));
-
-        let levels = ["Centro Pokémon (4x4)", "Cueva Oscura (8x8)", "Torre Victoria (12x12)"];
-        let descriptions = [
-            "Un laberinto básico para entrenadores novatos - Pequeño y manejable",
-            "Laberinto medio con obstáculos adicionales - Tamaño estándar",
-            "El desafío final para maestros Pokémon - Extenso y complejo",
+        let ui = Layout::of(d);
+        let mut d = d.begin_mode2D(ui.camera());
+        self.draw_background(&mut d, &ui, Color::new(25, 42, 86, 255));
+        self.draw_title(&mut d, "SELECCIONAR REGIÓN", 252, 102, 32, Color::BLACK, Color::new(255, 204, 51, 255));
+
+        let mut levels = vec![
+            ("Centro Pokémon (4x4)".to_string(), "Un laberinto básico para entrenadores novatos - Pequeño y manejable", "⭐ FÁCIL"),
+            ("Cueva Oscura (8x8)".to_string(), "Laberinto medio con obstáculos adicionales - Tamaño estándar", "⭐⭐ MEDIO"),
+            ("Torre Victoria (12x12)".to_string(), "El desafío final para maestros Pokémon - Extenso y complejo", "⭐⭐⭐ DIFÍCIL"),
         ];
-        let difficulties = ["⭐ FÁCIL", "⭐⭐ MEDIO", "⭐⭐⭐ DIFÍCIL"];
+        for level in &self.custom_levels {
+            let name = level.meta.name.clone().unwrap_or_else(|| "Nivel personalizado".to_string());
+            levels.push((name, "Nivel diseñado a mano - cargado desde levels/", "📁 PERSONALIZADO"));
+        }
 
-        for (i, ((level, desc), diff)) in levels.iter().zip(descriptions.iter()).zip(difficulties.iter()).enumerate() {
-            let y = 180 + (i * 100) as i32;
+        // Desplazar la lista para que la selección siempre quede visible
+        let first = self.selected_level.saturating_sub(VISIBLE_LEVELS - 1);
+        for (slot, (i, (level, desc, diff))) in levels.iter().enumerate().skip(first).take(VISIBLE_LEVELS).enumerate() {
+            let y = 180 + (slot * 100) as i32;
             let is_selected = i == self.selected_level;
 
-            self.draw_level_option(d, i, y, level, desc, diff, is_selected);
+            self.draw_level_option(&mut d, i, y, level, desc, diff, is_selected);
+        }
+        if levels.len() > VISIBLE_LEVELS {
+            d.draw_text(&format!("{}/{}", self.selected_level + 1, levels.len()), 680, 150, 16, Color::LIGHTGRAY);
         }
 
+        self.draw_seed_entry(&mut d);
+
         d.draw_rectangle(100, 520, 600, 60, Color::new(50, 50, 50, 200));
         d.draw_text("← → para cambiar región, ENTER para comenzar aventura", 120, 535, 18, Color::WHITE);
         d.draw_text("ESC para volver al menú principal", 250, 555, 16, Color::LIGHTGRAY);
     }
 
     pub fn draw_victory(&self, d: &mut RaylibDrawHandle) {
-        d.draw_rectangle(0, 0, 800, 600, Color::new(0, 0, 0, 180));
+        let ui = Layout::of(d);
+        let mut d = d.begin_mode2D(ui.camera());
+        d.draw_rectangle_rec(ui.design_area(), Color::new(0, 0, 0, 180));
         d.draw_rectangle(150, 120, 500, 360, Color::new(25, 42, 86, 255));
         d.draw_rectangle_lines_ex(Rectangle::new(150.0, 120.0, 500.0, 360.0), 5.0, Color::GOLD);
         d.draw_rectangle_lines_ex(Rectangle::new(155.0, 125.0, 490.0, 350.0), 2.0, Color::new(255, 204, 51, 255));
 
-        self.draw_title(d, "¡VICTORIA!", 302, 162, 48, Color::BLACK, Color::GOLD);
-        d.draw_text("¡Has completado la región!", 220, 220, 24, Color::WHITE);
-        d.draw_text("¡Eres un verdadero", 280, 260, 20, Color::new(255, 204, 51, 255));
-        d.draw_text("Maestro Pokémon!", 300, 280, 20, Color::new(255, 204, 51, 255));
+        self.draw_title(&mut d, "¡VICTORIA!", 302, 162, 48, Color::BLACK, Color::GOLD);
+        if let Some(winner) = self.race_winner {
+            let text = format!("¡El Jugador {} gana la carrera!", winner + 1);
+            let width = d.measure_text(&text, 24);
+            d.draw_text(&text, 400 - width / 2, 220, 24, RACER_COLORS[winner % RACER_COLORS.len()]);
+            d.draw_text("Llegó primero a la meta", 285, 260, 20, Color::new(255, 204, 51, 255));
+        } else {
+            d.draw_text("¡Has completado la región!", 220, 220, 24, Color::WHITE);
+            d.draw_text("¡Eres un verdadero", 280, 260, 20, Color::new(255, 204, 51, 255));
+            d.draw_text("Maestro Pokémon!", 300, 280, 20, Color::new(255, 204, 51, 255));
+        }
 
-        self.draw_statistics(d);
+        self.draw_statistics(&mut d);
 
         d.draw_rectangle(200, 420, 400, 40, Color::new(50, 50, 50, 200));
         d.draw_text("ESC: Volver al menú   |   R: Jugar de nuevo", 220, 435, 16, Color::WHITE);
 
-        self.draw_trophy_decorations(d);
-        self.draw_particle_effects(d);
+        self.draw_trophy_decorations(&mut d);
+        self.draw_particle_effects(&mut d);
     }
 
-    fn draw_background(&self, d: &mut RaylibDrawHandle, color: Color) {
+    pub fn draw_bindings(&self, d: &mut RaylibDrawHandle) {
+        let ui = Layout::of(d);
+        let mut d = d.begin_mode2D(ui.camera());
+        self.draw_background(&mut d, &ui, Color::new(25, 42, 86, 255));
+        self.draw_title(&mut d, "CONTROLES", 302, 102, 40, Color::BLACK, Color::new(255, 204, 51, 255));
+
+        // Scroll so the selected row stays on screen; the extra row is "Guardar y volver"
+        let rows = BINDING_ROWS + 1;
+        let first = self.selected_binding.saturating_sub(VISIBLE_BINDINGS - 1);
+        for (slot, i) in (first..rows).take(VISIBLE_BINDINGS).enumerate() {
+            let y = 160 + slot as i32 * 40;
+            let is_selected = i == self.selected_binding;
+            if is_selected {
+                d.draw_rectangle(60, y - 8, 680, 34, Color::new(255, 204, 51, 100));
+                d.draw_rectangle_lines_ex(Rectangle::new(60.0, (y - 8) as f32, 680.0, 34.0), 2.0, Color::new(255, 204, 51, 255));
+            }
+            let text_color = if is_selected { Color::new(255, 204, 51, 255) } else { Color::WHITE };
+            let prefix = if is_selected { "► " } else { "  " };
+            let Some((racer, action)) = binding_row(i) else {
+                d.draw_text(&format!("{}Guardar y volver", prefix), 80, y, 20, text_color);
+                continue;
+            };
+            d.draw_text(&format!("{}{}", prefix, binding_row_label(racer, action)), 80, y, 20, text_color);
+            let controls = if let Some(started) = self.capturing_binding.filter(|_| is_selected) {
+                let left = CAPTURE_TIMEOUT.saturating_sub(started.elapsed()).as_secs() + 1;
+                format!("Presiona una tecla o botón... (Volver cancela, {} s)", left)
+            } else {
+                let names: Vec<String> = self.row_bindings(racer).get(action).iter().map(Binding::name).collect();
+                if names.is_empty() { "(sin asignar)".to_string() } else { names.join(", ") }
+            };
+            d.draw_text(&controls, 340, y + 2, 16, if is_selected { Color::ORANGE } else { Color::LIGHTGRAY });
+        }
+        d.draw_text(&format!("{}/{}", self.selected_binding + 1, rows), 680, 130, 16, Color::LIGHTGRAY);
+
+        d.draw_rectangle(100, 490, 600, 90, Color::new(50, 50, 50, 200));
+        d.draw_text("↑ ↓ para elegir, ENTER para añadir una tecla o botón", 130, 505, 18, Color::WHITE);
+        d.draw_text("←: quitar el último control   R: controles por defecto", 170, 530, 16, Color::LIGHTGRAY);
+        d.draw_text("\"Guardar y volver\" los escribe en settings.json", 200, 552, 16, Color::LIGHTGRAY);
+    }
+
+    // Franjas a lo ancho de toda la ventana, aunque el diseño quede centrado
+    fn draw_background(&self, d: &mut RaylibDrawHandle, ui: &Layout, color: Color) {
         d.clear_background(color);
-        d.draw_rectangle(0, 0, 800, 100, Color::new(255, 204, 51, 255)); // Amarillo Pokémon
-        d.draw_rectangle(0, 90, 800, 20, Color::new(255, 165, 0, 255)); // Naranja
+        let area = ui.design_area();
+        d.draw_rectangle_rec(Rectangle::new(area.x, area.y, area.width, 100.0 - area.y), Color::new(255, 204, 51, 255)); // Amarillo Pokémon
+        d.draw_rectangle_rec(Rectangle::new(area.x, 90.0, area.width, 20.0), Color::new(255, 165, 0, 255)); // Naranja
     }
 
     fn draw_title(&self, d: &mut RaylibDrawHandle, title: &str, x: i32, y: i32, size: i32, shadow_color: Color, text_color: Color) {
@@ -284,6 +582,20 @@ impl GameStateManager {
         d.draw_text(title, x - 2, y - 2, size, text_color); // Texto
     }
 
+    fn draw_menu_options(&self, d: &mut RaylibDrawHandle) {
+        for (i, option) in self.menu_options.iter().enumerate() {
+            let y = 326 + i as i32 * 15;
+            let is_selected = i == self.selected_menu_option;
+            let (text, color) = if is_selected {
+                (format!("► {} ◄", option), Color::new(255, 204, 51, 255))
+            } else {
+                (option.clone(), Color::WHITE)
+            };
+            let width = d.measure_text(&text, 16);
+            d.draw_text(&text, 400 - width / 2, y, 16, color);
+        }
+    }
+
     fn draw_controls(&self, d: &mut RaylibDrawHandle) {
         d.draw_rectangle(150, 420, 500, 80, Color::new(50, 50, 50, 200));
         d.draw_text("CONTROLES:", 170, 435, 20, Color::new(255, 204, 51, 255));
@@ -327,11 +639,54 @@ impl GameStateManager {
         }
     }
 
+    fn draw_seed_entry(&self, d: &mut RaylibDrawHandle) {
+        let seed_text = if let Some(level) = self.selected_custom_level() {
+            format!("Semilla: {} (fija en el archivo)", level.seed)
+        } else if self.seed_input.is_empty() {
+            "Semilla: aleatoria (escribe números para fijarla)".to_string()
+        } else {
+            format!("Semilla: {}_", self.seed_input)
+        };
+        d.draw_text(&seed_text, 100, 482, 18, Color::new(255, 204, 51, 255));
+
+        if let Some(seed) = self.current_seed {
+            d.draw_text(&format!("Última: {}", seed), 100, 502, 14, Color::LIGHTGRAY);
+        }
+        if self.selected_custom_level().is_none() {
+            // Right of "Última", which can be as long as a 20-digit seed
+            let name = self.get_maze_algorithm().generator().name();
+            let color = if self.algorithm_override.is_some() { Color::ORANGE } else { Color::LIGHTGRAY };
+            d.draw_text(&format!("Algoritmo (TAB): {}", name), 420, 502, 14, color);
+        }
+    }
+
     fn draw_statistics(&self, d: &mut RaylibDrawHandle) {
-        d.draw_text("Estadísticas:", 200, 320, 18, Color::LIGHTGRAY);
-        d.draw_text("• Tiempo: --:--", 220, 345, 16, Color::WHITE);
-        d.draw_text("• Pokémon encontrados: ✓", 220, 365, 16, Color::WHITE);
-        d.draw_text("• Región completada: ✓", 220, 385, 16, Color::WHITE);
+        d.draw_text("Estadísticas:", 200, 315, 18, Color::LIGHTGRAY);
+        let time_text = match self.level_time {
+            Some(time) => {
+                let secs = time.as_secs();
+                let mut text = format!("• Tiempo: {:02}:{:02}", secs / 60, secs % 60);
+                if self.hints_used > 0 {
+                    text.push_str(&format!(" ({} pista(s), +{}s)", self.hints_used, (HINT_PENALTY * self.hints_used).as_secs()));
+                }
+                text
+            }
+            None => "• Tiempo: --:--".to_string(),
+        };
+        d.draw_text(&time_text, 220, 338, 16, Color::WHITE);
+        let (captured, total) = self.pokemon_captured;
+        let pokemon_text = if captured == total {
+            format!("• Pokémon capturados: {}/{} ✓", captured, total)
+        } else {
+            format!("• Pokémon capturados: {}/{}", captured, total)
+        };
+        if self.race_winner.is_none() {
+            d.draw_text(&pokemon_text, 220, 356, 16, Color::WHITE);
+        }
+        d.draw_text("• Región completada: ✓", 220, 374, 16, Color::WHITE);
+        if let Some(seed) = self.current_seed {
+            d.draw_text(&format!("• Semilla: {}", seed), 220, 392, 16, Color::WHITE);
+        }
     }
 
     fn draw_trophy_decorations(&self, d: &mut RaylibDrawHandle) {
@@ -354,3 +709,35 @@ impl GameStateManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::textures::wall_texture_names;
+
+    #[test]
+    fn every_algorithm_can_be_chosen() {
+        let mut game = GameStateManager::new();
+        game.selected_level = 0;
+        let region = game.get_maze_algorithm();
+        let mut seen = Vec::new();
+        for _ in 0..MazeAlgorithm::ALL.len() {
+            game.cycle_algorithm();
+            seen.push(game.get_maze_algorithm());
+        }
+        assert_eq!(seen, MazeAlgorithm::ALL);
+        game.cycle_algorithm();
+        assert_eq!((game.algorithm_override, game.get_maze_algorithm()), (None, region));
+    }
+
+    #[test]
+    fn horizontal_and_vertical_walls_differ_in_every_region() {
+        let mut game = GameStateManager::new();
+        for level in 0..BUILTIN_LEVELS {
+            game.selected_level = level;
+            let textures = wall_texture_names(&game.get_wall_textures());
+            assert_ne!(textures.get(&'-'), textures.get(&'|'), "región {}", level);
+            assert!(textures.contains_key(&'-') && textures.contains_key(&'|'));
+        }
+    }
+}
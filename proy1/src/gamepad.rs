@@ -0,0 +1,219 @@
+//! Estado de los gamepads en este frame.
+//!
+//! Los eventos de gilrs se leen una sola vez por frame, al principio del bucle, y quedan en
+//! una foto con los botones mantenidos, presionados y soltados de cada control y la posición
+//! de sus ejes. Menús, jugador y audio leen de la foto sin consumir eventos entre ellos.
+
+use gilrs::{Axis, Button, EventType, Gilrs};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::input::Binding;
+
+// How far an axis has to move to be taken as a new control on the controls screen
+const AXIS_PUSH: f32 = 0.5;
+
+#[derive(Debug, Clone, Default)]
+pub struct PadState {
+    pub name: String,
+    held: HashSet<Button>,
+    pressed: HashSet<Button>,  // Went down this frame
+    released: HashSet<Button>, // Went up this frame
+    axes: HashMap<Axis, f32>,
+    pushed: Vec<Binding>, // Axes that went past AXIS_PUSH this frame
+}
+
+impl PadState {
+    fn new(name: &str) -> Self {
+        PadState { name: name.to_string(), ..PadState::default() }
+    }
+
+    pub fn held(&self, button: Button) -> bool {
+        self.held.contains(&button)
+    }
+
+    pub fn pressed(&self, button: Button) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    pub fn released(&self, button: Button) -> bool {
+        self.released.contains(&button)
+    }
+
+    pub fn value(&self, axis: Axis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+
+    fn press(&mut self, button: Button) {
+        if self.held.insert(button) {
+            self.pressed.insert(button);
+        }
+    }
+
+    fn release(&mut self, button: Button) {
+        if self.held.remove(&button) {
+            self.released.insert(button);
+        }
+    }
+
+    fn move_axis(&mut self, axis: Axis, value: f32) {
+        let previous = self.axes.insert(axis, value).unwrap_or(0.0);
+        if value.abs() > AXIS_PUSH && previous.abs() <= AXIS_PUSH {
+            self.pushed.push(Binding::Axis { axis, positive: value > 0.0 });
+        }
+    }
+
+    // Edges only last one frame
+    fn start_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.pushed.clear();
+    }
+}
+
+/// Foto de todos los gamepads conectados, indexados por el id de gilrs.
+#[derive(Debug, Clone, Default)]
+pub struct Gamepads {
+    pads: BTreeMap<usize, PadState>,
+}
+
+impl Gamepads {
+    // Pads already connected at startup don't send a Connected event
+    pub fn new(gilrs: &Gilrs) -> Self {
+        let pads = gilrs.gamepads().map(|(id, gamepad)| (id.into(), PadState::new(gamepad.name()))).collect();
+        Gamepads { pads }
+    }
+
+    /// Consume todos los eventos pendientes de gilrs. Se llama una vez por frame, antes de leer.
+    pub fn update(&mut self, gilrs: &mut Gilrs) {
+        self.pads.values_mut().for_each(PadState::start_frame);
+        while let Some(event) = gilrs.next_event() {
+            let id: usize = event.id.into();
+            match event.event {
+                EventType::Connected => {
+                    let name = gilrs.gamepad(event.id).name().to_string();
+                    println!("🎮 Controller conectado: {}", name);
+                    self.pads.insert(id, PadState::new(&name));
+                }
+                EventType::Disconnected => {
+                    println!("🎮 Controller desconectado");
+                    self.pads.remove(&id);
+                }
+                EventType::ButtonPressed(button, _) if button != Button::Unknown => self.pad_mut(id).press(button),
+                EventType::ButtonReleased(button, _) => self.pad_mut(id).release(button),
+                EventType::AxisChanged(axis, value, _) if axis != Axis::Unknown => self.pad_mut(id).move_axis(axis, value),
+                _ => {}
+            }
+        }
+    }
+
+    fn pad_mut(&mut self, id: usize) -> &mut PadState {
+        self.pads.entry(id).or_default()
+    }
+
+    pub fn pads(&self) -> impl Iterator<Item = (usize, &PadState)> {
+        self.pads.iter().map(|(&id, pad)| (id, pad))
+    }
+
+    pub fn count(&self) -> usize {
+        self.pads.len()
+    }
+
+    /// Foto con sólo el `index`-ésimo control conectado (en orden de id), o vacía si no hay tantos.
+    /// En la carrera a pantalla dividida cada jugador lee únicamente el suyo.
+    pub fn only(&self, index: usize) -> Gamepads {
+        let pads = self.pads.iter().nth(index).map(|(&id, pad)| (id, pad.clone())).into_iter().collect();
+        Gamepads { pads }
+    }
+
+    // On any pad
+    pub fn held(&self, button: Button) -> bool {
+        self.pads.values().any(|pad| pad.held(button))
+    }
+
+    pub fn pressed(&self, button: Button) -> bool {
+        self.pads.values().any(|pad| pad.pressed(button))
+    }
+
+    pub fn released(&self, button: Button) -> bool {
+        self.pads.values().any(|pad| pad.released(button))
+    }
+
+    /// Primer botón presionado o eje movido a fondo en este frame, para la pantalla de Controles.
+    pub fn first_input(&self) -> Option<Binding> {
+        self.pads.values().find_map(|pad| {
+            // HashSet order is arbitrary, so with several buttons in one frame any of them may win
+            pad.pressed.iter().next().map(|&button| Binding::Button(button)).or(pad.pushed.first().copied())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pads() -> Gamepads {
+        let mut gamepads = Gamepads::default();
+        gamepads.pads.insert(0, PadState::new("uno"));
+        gamepads.pads.insert(3, PadState::new("dos"));
+        gamepads
+    }
+
+    #[test]
+    fn edges_last_one_frame() {
+        let mut gamepads = pads();
+        gamepads.pad_mut(0).press(Button::South);
+        assert!(gamepads.pressed(Button::South) && gamepads.held(Button::South));
+
+        gamepads.pads.values_mut().for_each(PadState::start_frame);
+        assert!(!gamepads.pressed(Button::South) && gamepads.held(Button::South));
+
+        gamepads.pad_mut(0).release(Button::South);
+        assert!(gamepads.released(Button::South) && !gamepads.held(Button::South));
+        gamepads.pads.values_mut().for_each(PadState::start_frame);
+        assert!(!gamepads.released(Button::South));
+    }
+
+    #[test]
+    fn pads_are_kept_apart() {
+        let mut gamepads = pads();
+        gamepads.pad_mut(3).press(Button::East);
+        gamepads.pad_mut(3).move_axis(Axis::LeftStickX, -0.8);
+        let (first, second) = (&gamepads.pads[&0], &gamepads.pads[&3]);
+        assert!(!first.held(Button::East) && second.pressed(Button::East));
+        assert_eq!((first.value(Axis::LeftStickX), second.value(Axis::LeftStickX)), (0.0, -0.8));
+        assert_eq!(gamepads.count(), 2);
+    }
+
+    #[test]
+    fn only_keeps_one_pad_by_position() {
+        let mut gamepads = pads();
+        gamepads.pad_mut(3).press(Button::South);
+        let second = gamepads.only(1);
+        assert_eq!(second.count(), 1);
+        assert!(second.pressed(Button::South));
+        assert!(!gamepads.only(0).held(Button::South));
+        assert_eq!(gamepads.only(2).count(), 0);
+    }
+
+    #[test]
+    fn repeated_press_is_not_a_new_edge() {
+        let mut pad = PadState::new("uno");
+        pad.press(Button::North);
+        pad.start_frame();
+        pad.press(Button::North); // Held the whole time, e.g. a duplicated event
+        assert!(!pad.pressed(Button::North));
+        pad.release(Button::West); // Never pressed
+        assert!(!pad.released(Button::West));
+    }
+
+    #[test]
+    fn axes_pushed_past_half_are_inputs() {
+        let mut gamepads = pads();
+        gamepads.pad_mut(0).move_axis(Axis::RightZ, 0.3);
+        assert_eq!(gamepads.first_input(), None);
+        gamepads.pad_mut(0).move_axis(Axis::RightZ, 0.9);
+        gamepads.pad_mut(0).move_axis(Axis::RightZ, 1.0);
+        assert_eq!(gamepads.first_input(), Some(Binding::Axis { axis: Axis::RightZ, positive: true }));
+        assert_eq!(gamepads.pads[&0].pushed.len(), 1);
+    }
+}
@@ -0,0 +1,285 @@
+//! Render sin ventana: laberinto, pose y texturas a un `Framebuffer` en memoria.
+//!
+//! La vista 3D y la 2D sólo escriben píxeles en CPU, así que no hace falta raylib
+//! abierto; aquí están la entrada `--render` para sacar un frame desde la terminal,
+//! la lectura/escritura de PPM (P6) y la comparación con tolerancia que usan las
+//! pruebas de imágenes de referencia (`tests/golden/`).
+
+use raylib::prelude::*;
+use std::f32::consts::PI;
+use std::fs;
+use std::path::Path;
+
+use crate::framebuffer::Framebuffer;
+use crate::game_state::GameStateManager;
+use crate::maze::Maze;
+use crate::npc::spawn_npcs;
+use crate::player::Player;
+use crate::render::{render_3d, Scene};
+use crate::sprites::SpriteManager;
+use crate::textures::TextureManager;
+
+pub const USAGE: &str = "Uso: proy1 --render salida.ppm [semilla]";
+
+/// `--render salida.ppm [semilla]`: dibuja el inicio de la primera región y lo guarda.
+///
+/// Sin semilla se usa 1, para que dos corridas den la misma imagen.
+pub fn render_cli(args: &[String], width: u32, height: u32, pokemon: usize) -> Result<String, String> {
+    let path = args.first().ok_or(USAGE)?;
+    let seed = match args.get(1) {
+        Some(seed) => seed.parse::<u64>().map_err(|_| format!("Semilla inválida '{}'. {}", seed, USAGE))?,
+        None => 1,
+    };
+
+    let game = GameStateManager::new();
+    let (maze_width, maze_height) = game.get_maze_size();
+    let maze = Maze::from_seed(maze_width, maze_height, game.get_maze_algorithm(), seed);
+    let (pos, a) = maze.spawn_pose();
+    let player = Player::new(pos, a, PI / 3.0);
+
+    let mut textures = TextureManager::headless();
+    textures.create_texture_caches_from_images();
+    textures.set_wall_textures(&game.get_wall_textures());
+    let surfaces = game.get_surface_style();
+    textures.preload_image(&surfaces.floor);
+    if let Some(ceiling) = &surfaces.ceiling {
+        textures.preload_image(ceiling);
+    }
+    let mut sprites = SpriteManager::new();
+    sprites.place_in_maze(&maze, pokemon);
+    let npcs = spawn_npcs(&maze, game.get_enemy_count());
+
+    let mut framebuffer = Framebuffer::new(width, height);
+    framebuffer.set_background_color(Color::new(50, 50, 100, 255));
+    framebuffer.clear();
+    let scene = Scene { surfaces: &surfaces, breadcrumbs: &[], sprites: &sprites, npcs: &npcs };
+    render_3d(&mut framebuffer, &player, &maze.map, &textures, &scene);
+
+    save_ppm(&framebuffer, Path::new(path))?;
+    Ok(path.clone())
+}
+
+/// Guarda el framebuffer como PPM binario (P6); el canal alfa se descarta.
+pub fn save_ppm(framebuffer: &Framebuffer, path: &Path) -> Result<(), String> {
+    let mut data = format!("P6\n{} {}\n255\n", framebuffer.width, framebuffer.height).into_bytes();
+    data.extend(framebuffer.pixels().iter().flat_map(|&[r, g, b, _]| [r, g, b]));
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("No se pudo crear {}: {}", dir.display(), e))?;
+    }
+    fs::write(path, data).map_err(|e| format!("No se pudo escribir {}: {}", path.display(), e))
+}
+
+/// Lee un PPM binario de 8 bits por canal, como los que escribe `save_ppm`.
+pub fn load_ppm(path: &Path) -> Result<Framebuffer, String> {
+    let data = fs::read(path).map_err(|e| format!("No se pudo leer {}: {}", path.display(), e))?;
+    parse_ppm(&data).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn parse_ppm(data: &[u8]) -> Result<Framebuffer, String> {
+    // Header: magic, width, height and max value, separated by whitespace or '#' comments
+    let mut fields = Vec::new();
+    let mut i = 0;
+    while fields.len() < 4 {
+        match data.get(i) {
+            None => return Err("cabecera incompleta".to_string()),
+            Some(b'#') => {
+                while data.get(i).is_some_and(|&c| c != b'\n') {
+                    i += 1;
+                }
+            }
+            Some(c) if c.is_ascii_whitespace() => i += 1,
+            Some(_) => {
+                let start = i;
+                while data.get(i).is_some_and(|c| !c.is_ascii_whitespace()) {
+                    i += 1;
+                }
+                fields.push(String::from_utf8_lossy(&data[start..i]).into_owned());
+            }
+        }
+    }
+    if fields[0] != "P6" || fields[3] != "255" {
+        return Err(format!("sólo se admite P6 de 8 bits, no {} {}", fields[0], fields[3]));
+    }
+    let number = |field: &str| field.parse::<u32>().map_err(|_| format!("tamaño inválido '{}'", field));
+    let (width, height) = (number(&fields[1])?, number(&fields[2])?);
+
+    // Exactly one whitespace byte separates the header from the pixels
+    let pixels = data.get(i + 1..).unwrap_or_default();
+    if pixels.len() != (width * height * 3) as usize {
+        return Err(format!("se esperaban {} bytes de píxeles y hay {}", width * height * 3, pixels.len()));
+    }
+    let mut framebuffer = Framebuffer::new(width, height);
+    for (index, rgb) in pixels.chunks_exact(3).enumerate() {
+        let (x, y) = (index as u32 % width, index as u32 / width);
+        framebuffer.set_pixel_with_color(x, y, Color::new(rgb[0], rgb[1], rgb[2], 255));
+    }
+    Ok(framebuffer)
+}
+
+// How far two images are apart: pixels past the tolerance, and the worst channel difference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageDiff {
+    pub differing: usize,
+    pub max_delta: u8,
+}
+
+/// Compara RGB píxel a píxel; cuenta los que se alejan más de `tolerance` en algún canal.
+pub fn compare(expected: &Framebuffer, actual: &Framebuffer, tolerance: u8) -> Result<ImageDiff, String> {
+    if (expected.width, expected.height) != (actual.width, actual.height) {
+        return Err(format!(
+            "tamaños distintos: {}x{} y {}x{}",
+            expected.width, expected.height, actual.width, actual.height
+        ));
+    }
+    let mut diff = ImageDiff { differing: 0, max_delta: 0 };
+    for (a, b) in expected.pixels().iter().zip(actual.pixels()) {
+        let delta = (0..3).map(|c| a[c].abs_diff(b[c])).max().unwrap_or(0);
+        diff.max_delta = diff.max_delta.max(delta);
+        if delta > tolerance {
+            diff.differing += 1;
+        }
+    }
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framebuffer::rgba;
+    use crate::maze::WORLD_BLOCK_SIZE;
+    use crate::npc::Npc;
+    use crate::render::render_2d;
+    use crate::sprites::SpriteSheet;
+    use crate::textures::SurfaceStyle;
+
+    const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+    // Failed renders are written here for a side-by-side look
+    const ACTUAL_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/target/golden");
+    // Per-channel difference that still counts as equal, e.g. for libm rounding on other platforms
+    const TOLERANCE: u8 = 2;
+    // Share of pixels that may go past TOLERANCE
+    const MAX_DIFFERING: f32 = 0.002;
+
+    // Every special wall, a goal door, open ground for sprites and an inner wall to hide behind
+    const ROWS: [&str; 7] = [
+        "+----D---+",
+        "|   g    |",
+        "| B   M  |",
+        "|        |",
+        "|  |--   |",
+        "|p       |",
+        "+--------+",
+    ];
+
+    fn maze() -> Maze {
+        Maze { seed: 3, ..Maze::from_rows(&ROWS) }
+    }
+
+    fn player() -> Player {
+        Player::new(Vector2::new(30.0, 110.0), -1.2, PI / 3.0)
+    }
+
+    fn render(surfaces: &SurfaceStyle, threads: usize) -> Framebuffer {
+        let maze = maze();
+        let textures = TextureManager::headless();
+        let mut sprites = SpriteManager::with_sheet(SpriteSheet::procedural());
+        sprites.place_in_maze(&maze, 3);
+        let npcs = [Npc::new((1, 1), 1)];
+        let scene = Scene { surfaces, breadcrumbs: &[(1, 4), (1, 3), (2, 3), (3, 3)], sprites: &sprites, npcs: &npcs };
+
+        let mut framebuffer = Framebuffer::new(160, 120);
+        framebuffer.set_render_threads(threads);
+        render_3d(&mut framebuffer, &player(), &maze.map, &textures, &scene);
+        framebuffer
+    }
+
+    fn courtyard() -> SurfaceStyle {
+        SurfaceStyle { floor: "wall3.jpg".to_string(), ceiling: None }
+    }
+
+    // UPDATE_GOLDEN=1 cargo test rewrites the reference images instead of checking them
+    fn check_golden(name: &str, actual: &Framebuffer) {
+        let path = Path::new(GOLDEN_DIR).join(format!("{}.ppm", name));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            save_ppm(actual, &path).unwrap();
+            return;
+        }
+        let expected = load_ppm(&path).unwrap_or_else(|e| panic!("{} (UPDATE_GOLDEN=1 la genera)", e));
+        let diff = compare(&expected, actual, TOLERANCE).unwrap();
+        let allowed = (MAX_DIFFERING * (actual.width * actual.height) as f32) as usize;
+        if diff.differing > allowed {
+            let dump = Path::new(ACTUAL_DIR).join(format!("{}.ppm", name));
+            save_ppm(actual, &dump).unwrap();
+            panic!("{}: {} píxeles distintos (máx. {}), peor canal {}; ver {}", name, diff.differing, allowed, diff.max_delta, dump.display());
+        }
+    }
+
+    #[test]
+    fn courtyard_matches_golden() {
+        check_golden("courtyard", &render(&courtyard(), 1));
+    }
+
+    #[test]
+    fn cave_with_ceiling_matches_golden() {
+        let cave = SurfaceStyle { floor: "brick".to_string(), ceiling: Some("mossy".to_string()) };
+        check_golden("cave", &render(&cave, 1));
+    }
+
+    #[test]
+    fn top_down_view_matches_golden() {
+        let mut framebuffer = Framebuffer::new(320, 224);
+        framebuffer.clear();
+        render_2d(&mut framebuffer, &player(), &maze().map);
+        check_golden("top_down", &framebuffer);
+    }
+
+    #[test]
+    fn top_down_view_fits_a_half_scale_framebuffer() {
+        // An 8x8 region maze is 17x17 cells; 800x600 at render_scale 0.5 leaves 400x300 pixels
+        let side = 17;
+        let map: Vec<Vec<char>> = (0..side)
+            .map(|row| (0..side).map(|col| if row == 0 || col == 0 || row == side - 1 || col == side - 1 { '+' } else { ' ' }).collect())
+            .collect();
+        let centre = WORLD_BLOCK_SIZE as f32 * side as f32 / 2.0;
+        let mut framebuffer = Framebuffer::new(400, 300);
+        framebuffer.clear();
+        render_2d(&mut framebuffer, &Player::new(Vector2::new(centre, centre), 0.0, PI / 3.0), &map);
+
+        let empty = rgba(Color::BLACK);
+        let block = 300 / side as u32;
+        let far_corner = (side as u32 - 1) * block + block / 2;
+        assert_ne!(rgba(framebuffer.get_pixel(far_corner, far_corner)), empty, "la última celda queda fuera");
+        assert_eq!(rgba(framebuffer.get_pixel(far_corner, side as u32 * block + 1)), empty);
+    }
+
+    #[test]
+    fn threads_render_the_same_frame() {
+        let single = render(&courtyard(), 1);
+        let split = render(&courtyard(), 5);
+        assert_eq!(single.pixels(), split.pixels());
+    }
+
+    #[test]
+    fn ppm_round_trip() {
+        let mut framebuffer = Framebuffer::new(3, 2);
+        framebuffer.set_pixel_with_color(2, 1, Color::new(10, 20, 30, 255));
+        let path = Path::new(ACTUAL_DIR).join("round_trip.ppm");
+        save_ppm(&framebuffer, &path).unwrap();
+        let loaded = load_ppm(&path).unwrap();
+        assert_eq!(loaded.pixels(), framebuffer.pixels());
+
+        assert!(parse_ppm(b"P6\n# comentario\n1 1\n255\n\x01\x02\x03").is_ok());
+        assert!(parse_ppm(b"P3\n1 1\n255\n1 2 3").is_err());
+        assert!(parse_ppm(b"P6\n2 2\n255\n\x01\x02\x03").is_err());
+    }
+
+    #[test]
+    fn compare_counts_pixels_past_tolerance() {
+        let a = Framebuffer::new(2, 2);
+        let mut b = Framebuffer::new(2, 2);
+        b.set_pixel_with_color(0, 0, Color::new(2, 0, 0, 255));
+        b.set_pixel_with_color(1, 1, Color::new(0, 0, 9, 255));
+        assert_eq!(compare(&a, &b, 2), Ok(ImageDiff { differing: 1, max_delta: 9 }));
+        assert!(compare(&a, &Framebuffer::new(2, 3), 2).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use raylib::prelude::Vector2;
+
+use crate::solver;
+
+// Cells of the path shown per hint
+pub const HINT_STEPS: usize = 10;
+// How long breadcrumbs stay visible after asking for a hint
+pub const HINT_DURATION: Duration = Duration::from_secs(6);
+// Time added to the level clock for each hint
+pub const HINT_PENALTY: Duration = Duration::from_secs(15);
+
+/// Pistas del nivel en curso: migas de pan hacia la meta que cuestan tiempo.
+#[derive(Debug, Default)]
+pub struct Hints {
+    breadcrumbs: Vec<(usize, usize)>,
+    remaining: Duration,
+    pub used: u32,
+}
+
+impl Hints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Ask for a hint; ignored while one is still showing so it isn't charged twice
+    pub fn request(&mut self) -> bool {
+        if self.is_active() {
+            return false;
+        }
+        self.used += 1;
+        self.remaining = HINT_DURATION;
+        true
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.remaining.is_zero()
+    }
+
+    // Follow the player: the trail is recomputed from wherever they are now
+    pub fn update(&mut self, maze: &[Vec<char>], player_pos: Vector2, block_size: f32, dt: Duration) {
+        self.remaining = self.remaining.saturating_sub(dt);
+        self.breadcrumbs.clear();
+        if !self.is_active() {
+            return;
+        }
+        if let Some(path) = solver::path_to_goal(maze, player_pos, block_size) {
+            // Skip the cell the player is standing on
+            self.breadcrumbs.extend(path.into_iter().skip(1).take(HINT_STEPS));
+        }
+    }
+
+    pub fn breadcrumbs(&self) -> &[(usize, usize)] {
+        &self.breadcrumbs
+    }
+
+    pub fn penalty(&self) -> Duration {
+        HINT_PENALTY * self.used
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Maze;
+
+    #[test]
+    fn hint_shows_next_steps_then_expires() {
+        let map = Maze::from_rows(&["+------+", "|p    g|", "+------+"]).map;
+        let mut hints = Hints::new();
+        assert!(hints.request());
+        assert!(!hints.request()); // Still showing, not charged again
+        assert_eq!(hints.used, 1);
+
+        hints.update(&map, Vector2::new(30.0, 30.0), 20.0, Duration::from_secs(1));
+        assert_eq!(hints.breadcrumbs(), [(2, 1), (3, 1), (4, 1), (5, 1), (6, 1)]);
+
+        hints.update(&map, Vector2::new(30.0, 30.0), 20.0, HINT_DURATION);
+        assert!(hints.breadcrumbs().is_empty());
+        assert_eq!(hints.penalty(), HINT_PENALTY);
+    }
+}
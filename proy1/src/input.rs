@@ -0,0 +1,702 @@
+//! Acciones del juego (avanzar, girar, mapa, siguiente canción...) y los controles que las disparan.
+//!
+//! Cada acción acepta cualquier mezcla de teclas, botones del mouse, botones del gamepad y
+//! medios ejes de los sticks o gatillos. Los controles se guardan en `settings.json` bajo
+//! `"bindings"` con nombres como `"Key:W"`, `"Mouse:LEFT"`, `"Pad:South"` o `"Pad:LeftStickY+"`,
+//! y se cambian en la pantalla de Controles.
+
+use gilrs::{Axis, Button};
+use raylib::prelude::*;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::gamepad::Gamepads;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    StrafeLeft,
+    StrafeRight,
+    TurnLeft,
+    TurnRight,
+    Sprint,
+    ToggleMap,
+    ToggleMouse,
+    Hint,
+    NextTrack,
+    PreviousTrack,
+    ToggleAudio,
+    SaveLevel,
+    Screenshot,
+    Record,
+    Fullscreen,
+    GamepadTest,
+    MenuUp,
+    MenuDown,
+    MenuLeft,
+    MenuRight,
+    Accept,
+    Back,
+    Restart,
+    CycleAlgorithm,
+}
+
+impl Action {
+    pub const ALL: [Action; 26] = [
+        Action::MoveForward,
+        Action::MoveBackward,
+        Action::StrafeLeft,
+        Action::StrafeRight,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::Sprint,
+        Action::ToggleMap,
+        Action::ToggleMouse,
+        Action::Hint,
+        Action::NextTrack,
+        Action::PreviousTrack,
+        Action::ToggleAudio,
+        Action::SaveLevel,
+        Action::Screenshot,
+        Action::Record,
+        Action::Fullscreen,
+        Action::GamepadTest,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::MenuLeft,
+        Action::MenuRight,
+        Action::Accept,
+        Action::Back,
+        Action::Restart,
+        Action::CycleAlgorithm,
+    ];
+
+    // What each player controls on their own in the split-screen race
+    pub const MOVEMENT: [Action; 7] = [
+        Action::MoveForward,
+        Action::MoveBackward,
+        Action::StrafeLeft,
+        Action::StrafeRight,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::Sprint,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveForward => "Avanzar",
+            Action::MoveBackward => "Retroceder",
+            Action::StrafeLeft => "Paso a la izquierda",
+            Action::StrafeRight => "Paso a la derecha",
+            Action::TurnLeft => "Girar a la izquierda",
+            Action::TurnRight => "Girar a la derecha",
+            Action::Sprint => "Correr",
+            Action::ToggleMap => "Vista 2D/3D",
+            Action::ToggleMouse => "Mostrar/ocultar cursor",
+            Action::Hint => "Pista",
+            Action::NextTrack => "Siguiente canción",
+            Action::PreviousTrack => "Canción anterior",
+            Action::ToggleAudio => "Activar/desactivar audio",
+            Action::SaveLevel => "Guardar laberinto",
+            Action::Screenshot => "Captura de pantalla",
+            Action::Record => "Grabar secuencia",
+            Action::Fullscreen => "Pantalla completa",
+            Action::GamepadTest => "Probar gamepad",
+            Action::MenuUp => "Menú: arriba",
+            Action::MenuDown => "Menú: abajo",
+            Action::MenuLeft => "Menú: izquierda",
+            Action::MenuRight => "Menú: derecha",
+            Action::Accept => "Aceptar",
+            Action::Back => "Volver",
+            Action::Restart => "Reiniciar / por defecto",
+            Action::CycleAlgorithm => "Cambiar algoritmo",
+        }
+    }
+
+    fn name(self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn default_bindings(self) -> Vec<Binding> {
+        use Binding::{Button as Pad, Key};
+        use KeyboardKey::*;
+        let stick = |axis, positive| Binding::Axis { axis, positive };
+        match self {
+            Action::MoveForward => vec![Key(KEY_W), Key(KEY_UP), stick(Axis::LeftStickY, true)],
+            Action::MoveBackward => vec![Key(KEY_S), Key(KEY_DOWN), stick(Axis::LeftStickY, false)],
+            Action::StrafeLeft => vec![Key(KEY_A), stick(Axis::LeftStickX, false)],
+            Action::StrafeRight => vec![Key(KEY_D), stick(Axis::LeftStickX, true)],
+            Action::TurnLeft => vec![Key(KEY_RIGHT), stick(Axis::RightStickX, false), stick(Axis::RightZ, true)],
+            Action::TurnRight => vec![Key(KEY_LEFT), stick(Axis::RightStickX, true), stick(Axis::LeftZ, true)],
+            Action::Sprint => vec![Key(KEY_LEFT_SHIFT), Pad(Button::LeftThumb)],
+            Action::ToggleMap => vec![Key(KEY_M)],
+            Action::ToggleMouse => vec![Key(KEY_C)],
+            Action::Hint => vec![Key(KEY_H)],
+            Action::NextTrack => vec![Key(KEY_N), Pad(Button::RightTrigger2)],
+            Action::PreviousTrack => vec![Key(KEY_P), Pad(Button::LeftTrigger2)],
+            Action::ToggleAudio => vec![Key(KEY_SPACE), Pad(Button::Start)],
+            Action::SaveLevel => vec![Key(KEY_F5)],
+            Action::Screenshot => vec![Key(KEY_F12)],
+            Action::Record => vec![Key(KEY_F9)],
+            Action::Fullscreen => vec![Key(KEY_F11)],
+            Action::GamepadTest => vec![Key(KEY_G)],
+            Action::MenuUp => vec![Key(KEY_UP), Pad(Button::DPadUp)],
+            Action::MenuDown => vec![Key(KEY_DOWN), Pad(Button::DPadDown)],
+            Action::MenuLeft => vec![Key(KEY_LEFT), Pad(Button::DPadLeft)],
+            Action::MenuRight => vec![Key(KEY_RIGHT), Pad(Button::DPadRight)],
+            Action::Accept => vec![Key(KEY_ENTER), Pad(Button::South)],
+            Action::Back => vec![Key(KEY_ESCAPE), Pad(Button::East)],
+            Action::Restart => vec![Key(KEY_R), Pad(Button::North)],
+            Action::CycleAlgorithm => vec![Key(KEY_TAB), Pad(Button::West)],
+        }
+    }
+}
+
+/// Un control físico: tecla, botón del mouse, botón del gamepad o la mitad de un eje.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    Key(KeyboardKey),
+    Mouse(MouseButton),
+    Button(Button),
+    Axis { axis: Axis, positive: bool }, // Only the positive or the negative half of the axis
+}
+
+const MOUSE_BUTTONS: [MouseButton; 7] = [
+    MouseButton::MOUSE_BUTTON_LEFT,
+    MouseButton::MOUSE_BUTTON_RIGHT,
+    MouseButton::MOUSE_BUTTON_MIDDLE,
+    MouseButton::MOUSE_BUTTON_SIDE,
+    MouseButton::MOUSE_BUTTON_EXTRA,
+    MouseButton::MOUSE_BUTTON_FORWARD,
+    MouseButton::MOUSE_BUTTON_BACK,
+];
+
+const PAD_BUTTONS: [Button; 19] = [
+    Button::South,
+    Button::East,
+    Button::North,
+    Button::West,
+    Button::C,
+    Button::Z,
+    Button::LeftTrigger,
+    Button::LeftTrigger2,
+    Button::RightTrigger,
+    Button::RightTrigger2,
+    Button::Select,
+    Button::Start,
+    Button::Mode,
+    Button::LeftThumb,
+    Button::RightThumb,
+    Button::DPadUp,
+    Button::DPadDown,
+    Button::DPadLeft,
+    Button::DPadRight,
+];
+
+const PAD_AXES: [Axis; 8] = [
+    Axis::LeftStickX,
+    Axis::LeftStickY,
+    Axis::LeftZ,
+    Axis::RightStickX,
+    Axis::RightStickY,
+    Axis::RightZ,
+    Axis::DPadX,
+    Axis::DPadY,
+];
+
+// Turning speed with a stick pushed all the way, relative to the turn keys
+const STICK_TURN_GAIN: f32 = 2.0;
+
+
+impl Binding {
+    /// Nombre en `settings.json` y en la pantalla de Controles.
+    pub fn name(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("Key:{}", format!("{:?}", key).trim_start_matches("KEY_")),
+            Binding::Mouse(button) => format!("Mouse:{}", format!("{:?}", button).trim_start_matches("MOUSE_BUTTON_")),
+            Binding::Button(button) => format!("Pad:{:?}", button),
+            Binding::Axis { axis, positive } => format!("Pad:{:?}{}", axis, if *positive { '+' } else { '-' }),
+        }
+    }
+
+    // Gamepad button or axis, as opposed to keyboard and mouse
+    pub fn is_pad(&self) -> bool {
+        matches!(self, Binding::Button(_) | Binding::Axis { .. })
+    }
+
+    pub fn parse(name: &str) -> Option<Binding> {
+        let (kind, control) = name.split_once(':')?;
+        let candidates: Vec<Binding> = match kind {
+            "Key" => return key_from_name(control).map(Binding::Key),
+            "Mouse" => MOUSE_BUTTONS.into_iter().map(Binding::Mouse).collect(),
+            "Pad" => PAD_BUTTONS
+                .into_iter()
+                .map(Binding::Button)
+                .chain(PAD_AXES.into_iter().flat_map(|axis| [true, false].map(|positive| Binding::Axis { axis, positive })))
+                .collect(),
+            _ => return None,
+        };
+        candidates.into_iter().find(|binding| binding.name().split_once(':').map(|(_, c)| c) == Some(control))
+    }
+}
+
+// Inverse of the `Key:` names: the raylib constant without its KEY_ prefix
+fn key_from_name(name: &str) -> Option<KeyboardKey> {
+    use KeyboardKey::*;
+    let key = match name {
+        "APOSTROPHE" => KEY_APOSTROPHE,
+        "COMMA" => KEY_COMMA,
+        "MINUS" => KEY_MINUS,
+        "PERIOD" => KEY_PERIOD,
+        "SLASH" => KEY_SLASH,
+        "ZERO" => KEY_ZERO,
+        "ONE" => KEY_ONE,
+        "TWO" => KEY_TWO,
+        "THREE" => KEY_THREE,
+        "FOUR" => KEY_FOUR,
+        "FIVE" => KEY_FIVE,
+        "SIX" => KEY_SIX,
+        "SEVEN" => KEY_SEVEN,
+        "EIGHT" => KEY_EIGHT,
+        "NINE" => KEY_NINE,
+        "SEMICOLON" => KEY_SEMICOLON,
+        "EQUAL" => KEY_EQUAL,
+        "A" => KEY_A,
+        "B" => KEY_B,
+        "C" => KEY_C,
+        "D" => KEY_D,
+        "E" => KEY_E,
+        "F" => KEY_F,
+        "G" => KEY_G,
+        "H" => KEY_H,
+        "I" => KEY_I,
+        "J" => KEY_J,
+        "K" => KEY_K,
+        "L" => KEY_L,
+        "M" => KEY_M,
+        "N" => KEY_N,
+        "O" => KEY_O,
+        "P" => KEY_P,
+        "Q" => KEY_Q,
+        "R" => KEY_R,
+        "S" => KEY_S,
+        "T" => KEY_T,
+        "U" => KEY_U,
+        "V" => KEY_V,
+        "W" => KEY_W,
+        "X" => KEY_X,
+        "Y" => KEY_Y,
+        "Z" => KEY_Z,
+        "LEFT_BRACKET" => KEY_LEFT_BRACKET,
+        "BACKSLASH" => KEY_BACKSLASH,
+        "RIGHT_BRACKET" => KEY_RIGHT_BRACKET,
+        "GRAVE" => KEY_GRAVE,
+        "SPACE" => KEY_SPACE,
+        "ESCAPE" => KEY_ESCAPE,
+        "ENTER" => KEY_ENTER,
+        "TAB" => KEY_TAB,
+        "BACKSPACE" => KEY_BACKSPACE,
+        "INSERT" => KEY_INSERT,
+        "DELETE" => KEY_DELETE,
+        "RIGHT" => KEY_RIGHT,
+        "LEFT" => KEY_LEFT,
+        "DOWN" => KEY_DOWN,
+        "UP" => KEY_UP,
+        "PAGE_UP" => KEY_PAGE_UP,
+        "PAGE_DOWN" => KEY_PAGE_DOWN,
+        "HOME" => KEY_HOME,
+        "END" => KEY_END,
+        "CAPS_LOCK" => KEY_CAPS_LOCK,
+        "SCROLL_LOCK" => KEY_SCROLL_LOCK,
+        "NUM_LOCK" => KEY_NUM_LOCK,
+        "PRINT_SCREEN" => KEY_PRINT_SCREEN,
+        "PAUSE" => KEY_PAUSE,
+        "F1" => KEY_F1,
+        "F2" => KEY_F2,
+        "F3" => KEY_F3,
+        "F4" => KEY_F4,
+        "F5" => KEY_F5,
+        "F6" => KEY_F6,
+        "F7" => KEY_F7,
+        "F8" => KEY_F8,
+        "F9" => KEY_F9,
+        "F10" => KEY_F10,
+        "F11" => KEY_F11,
+        "F12" => KEY_F12,
+        "LEFT_SHIFT" => KEY_LEFT_SHIFT,
+        "LEFT_CONTROL" => KEY_LEFT_CONTROL,
+        "LEFT_ALT" => KEY_LEFT_ALT,
+        "LEFT_SUPER" => KEY_LEFT_SUPER,
+        "RIGHT_SHIFT" => KEY_RIGHT_SHIFT,
+        "RIGHT_CONTROL" => KEY_RIGHT_CONTROL,
+        "RIGHT_ALT" => KEY_RIGHT_ALT,
+        "RIGHT_SUPER" => KEY_RIGHT_SUPER,
+        "KB_MENU" => KEY_KB_MENU,
+        "KP_0" => KEY_KP_0,
+        "KP_1" => KEY_KP_1,
+        "KP_2" => KEY_KP_2,
+        "KP_3" => KEY_KP_3,
+        "KP_4" => KEY_KP_4,
+        "KP_5" => KEY_KP_5,
+        "KP_6" => KEY_KP_6,
+        "KP_7" => KEY_KP_7,
+        "KP_8" => KEY_KP_8,
+        "KP_9" => KEY_KP_9,
+        "KP_DECIMAL" => KEY_KP_DECIMAL,
+        "KP_DIVIDE" => KEY_KP_DIVIDE,
+        "KP_MULTIPLY" => KEY_KP_MULTIPLY,
+        "KP_SUBTRACT" => KEY_KP_SUBTRACT,
+        "KP_ADD" => KEY_KP_ADD,
+        "KP_ENTER" => KEY_KP_ENTER,
+        "KP_EQUAL" => KEY_KP_EQUAL,
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Controles de cada acción; las acciones sin entrada en el archivo usan los de por defecto.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bindings {
+    map: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Bindings { map: Action::ALL.iter().map(|&action| (action, action.default_bindings())).collect() }
+    }
+}
+
+impl Bindings {
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.map.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    // Adds the control unless the action already has it
+    pub fn add(&mut self, action: Action, binding: Binding) {
+        let bindings = self.map.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    // Removes the most recently added control
+    pub fn remove_last(&mut self, action: Action) -> Option<Binding> {
+        self.map.get_mut(&action)?.pop()
+    }
+
+    /// Lee el objeto `"bindings"`; los nombres que no se reconocen se omiten con un aviso.
+    pub fn from_json(value: &Value) -> Result<Self, String> {
+        Bindings::from_json_over(value, Bindings::default())
+    }
+
+    /// Como `from_json`, pero las acciones que faltan toman los controles de `defaults`
+    /// y sólo se leen las acciones que `defaults` tiene.
+    pub fn from_json_over(value: &Value, defaults: Bindings) -> Result<Self, String> {
+        let fields = value.as_object().ok_or("\"bindings\" debe ser un objeto")?;
+        let mut bindings = defaults;
+        let actions: Vec<Action> = bindings.map.keys().copied().collect();
+        for action in actions {
+            let Some(names) = fields.get(&action.name()) else { continue };
+            let names = names.as_array().ok_or_else(|| format!("\"{}\" debe ser una lista", action.name()))?;
+            let parsed = names.iter().filter_map(|name| {
+                let binding = name.as_str().and_then(Binding::parse);
+                if binding.is_none() {
+                    println!("⚠️ Control desconocido {} para {}, ignorado", name, action.name());
+                }
+                binding
+            });
+            bindings.map.insert(action, parsed.collect());
+        }
+        Ok(bindings)
+    }
+
+    /// Mitad del teclado de un jugador en la carrera a pantalla dividida, si no se cambió en
+    /// `settings.json` (jugador 1: WASD, Q/E para girar y Shift izquierdo para correr; jugador 2:
+    /// IJKL, U/O, las flechas y Shift derecho). Sólo tiene las acciones de movimiento.
+    pub fn racer_keys(racer: usize) -> Bindings {
+        use KeyboardKey::*;
+        let keys: &[(Action, &[KeyboardKey])] = if racer == 0 {
+            &[
+                (Action::MoveForward, &[KEY_W]),
+                (Action::MoveBackward, &[KEY_S]),
+                (Action::StrafeLeft, &[KEY_A]),
+                (Action::StrafeRight, &[KEY_D]),
+                (Action::TurnLeft, &[KEY_Q]),
+                (Action::TurnRight, &[KEY_E]),
+                (Action::Sprint, &[KEY_LEFT_SHIFT]),
+            ]
+        } else {
+            &[
+                (Action::MoveForward, &[KEY_I, KEY_UP]),
+                (Action::MoveBackward, &[KEY_K, KEY_DOWN]),
+                (Action::StrafeLeft, &[KEY_J]),
+                (Action::StrafeRight, &[KEY_L]),
+                (Action::TurnLeft, &[KEY_U, KEY_LEFT]),
+                (Action::TurnRight, &[KEY_O, KEY_RIGHT]),
+                (Action::Sprint, &[KEY_RIGHT_SHIFT]),
+            ]
+        };
+        let map = keys.iter().map(|&(action, keys)| (action, keys.iter().map(|&key| Binding::Key(key)).collect())).collect();
+        Bindings { map }
+    }
+
+    /// Controles de un jugador en la carrera: sólo las acciones de movimiento, con las teclas y
+    /// botones del mouse de `keys` (su mitad del teclado) y los botones y ejes de gamepad configurados.
+    pub fn for_racer(&self, keys: &Bindings) -> Bindings {
+        let map = Action::MOVEMENT
+            .iter()
+            .map(|&action| {
+                // The pad half comes from the shared controls; each racer's Input only sees their own pad
+                let own = keys.get(action).iter().filter(|b| !b.is_pad());
+                let pad = self.get(action).iter().filter(|b| b.is_pad());
+                (action, own.chain(pad).copied().collect())
+            })
+            .collect();
+        Bindings { map }
+    }
+
+    /// Primera tecla de avanzar, retroceder, paso a cada lado y giro a cada lado, para los
+    /// textos de ayuda: `"W/S/A/D + Q/E"`.
+    pub fn key_hint(&self) -> String {
+        let first_key = |action: Action| {
+            let key = self.get(action).iter().find(|b| matches!(b, Binding::Key(_)));
+            key.map_or("-".to_string(), |key| key.name().trim_start_matches("Key:").to_string())
+        };
+        let keys = |actions: &[Action]| actions.iter().map(|&action| first_key(action)).collect::<Vec<_>>().join("/");
+        format!(
+            "{} + {}",
+            keys(&[Action::MoveForward, Action::MoveBackward, Action::StrafeLeft, Action::StrafeRight]),
+            keys(&[Action::TurnLeft, Action::TurnRight])
+        )
+    }
+
+    pub fn to_json(&self) -> Value {
+        let fields = self
+            .map
+            .iter()
+            .map(|(action, bindings)| (action.name(), bindings.iter().map(|b| Value::from(b.name())).collect()))
+            .collect();
+        Value::Object(fields)
+    }
+}
+
+/// Estado de las acciones en este frame y el anterior, para saber cuáles se acaban de presionar.
+pub struct Input {
+    values: [f32; Action::ALL.len()],
+    previous: [f32; Action::ALL.len()],
+}
+
+impl Input {
+    pub fn new() -> Self {
+        Input { values: [0.0; Action::ALL.len()], previous: [0.0; Action::ALL.len()] }
+    }
+
+    /// Lee todos los controles una vez por frame. Los ejes por debajo de `deadzone` cuentan como 0.
+    pub fn update(&mut self, rl: &RaylibHandle, gamepads: &Gamepads, bindings: &Bindings, deadzone: f32) {
+        self.previous = self.values;
+        for action in Action::ALL {
+            self.values[action as usize] = bindings
+                .get(action)
+                .iter()
+                .map(|binding| binding_value(*binding, rl, gamepads, deadzone) * turn_gain(action, *binding))
+                .fold(0.0, f32::max);
+        }
+    }
+
+    // 0.0 to 1.0 (up to STICK_TURN_GAIN for turning with a stick); keys and buttons are all or
+    // nothing, sticks and triggers in between
+    pub fn value(&self, action: Action) -> f32 {
+        self.values[action as usize]
+    }
+
+    pub fn held(&self, action: Action) -> bool {
+        self.value(action) > 0.0
+    }
+
+    // Held now but not in the previous frame
+    pub fn pressed(&self, action: Action) -> bool {
+        self.held(action) && self.previous[action as usize] <= 0.0
+    }
+
+    #[cfg(test)]
+    pub fn set(&mut self, action: Action, value: f32) {
+        self.previous = self.values;
+        self.values[action as usize] = value;
+    }
+}
+
+// Sticks turn the camera faster than keys and triggers
+fn turn_gain(action: Action, binding: Binding) -> f32 {
+    let turning = matches!(action, Action::TurnLeft | Action::TurnRight);
+    let stick = matches!(binding, Binding::Axis { axis: Axis::LeftStickX | Axis::LeftStickY | Axis::RightStickX | Axis::RightStickY, .. });
+    if turning && stick { STICK_TURN_GAIN } else { 1.0 }
+}
+
+fn binding_value(binding: Binding, rl: &RaylibHandle, gamepads: &Gamepads, deadzone: f32) -> f32 {
+    let pressed = |down: bool| if down { 1.0 } else { 0.0 };
+    match binding {
+        Binding::Key(key) => pressed(rl.is_key_down(key)),
+        Binding::Mouse(button) => pressed(rl.is_mouse_button_down(button)),
+        // A tap that goes down and up within one frame still counts for that frame
+        Binding::Button(button) => pressed(gamepads.held(button) || gamepads.pressed(button)),
+        Binding::Axis { axis, positive } => gamepads
+            .pads()
+            .map(|(_id, pad)| pad.value(axis) * if positive { 1.0 } else { -1.0 })
+            .filter(|&value| value > deadzone)
+            .fold(0.0, f32::max)
+            .min(1.0),
+    }
+}
+
+/// Primer control que se presione en este frame, para la pantalla de Controles.
+pub fn pressed_binding(rl: &mut RaylibHandle, gamepads: &Gamepads) -> Option<Binding> {
+    if let Some(key) = rl.get_key_pressed() {
+        return Some(Binding::Key(key));
+    }
+    MOUSE_BUTTONS
+        .into_iter()
+        .find(|&button| rl.is_mouse_button_pressed(button))
+        .map(Binding::Mouse)
+        .or_else(|| gamepads.first_input())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binding_names_round_trip() {
+        let bindings = [
+            Binding::Key(KeyboardKey::KEY_W),
+            Binding::Key(KeyboardKey::KEY_LEFT_SHIFT),
+            Binding::Mouse(MouseButton::MOUSE_BUTTON_RIGHT),
+            Binding::Button(Button::South),
+            Binding::Axis { axis: Axis::LeftStickY, positive: false },
+        ];
+        let names: Vec<String> = bindings.iter().map(Binding::name).collect();
+        assert_eq!(names, ["Key:W", "Key:LEFT_SHIFT", "Mouse:RIGHT", "Pad:South", "Pad:LeftStickY-"]);
+        for (binding, name) in bindings.iter().zip(&names) {
+            assert_eq!(Binding::parse(name).as_ref(), Some(binding));
+        }
+        assert_eq!(Binding::parse("Key:NOPE"), None);
+        assert_eq!(Binding::parse("W"), None);
+    }
+
+    #[test]
+    fn every_key_name_parses_back() {
+        // KEY_KB_MENU (348) is the highest key code
+        let keys: Vec<KeyboardKey> = (1..=348).filter_map(raylib::core::input::key_from_i32).collect();
+        assert!(keys.len() > 100);
+        for key in keys {
+            let name = Binding::Key(key).name();
+            assert_eq!(Binding::parse(&name), Some(Binding::Key(key)), "{}", name);
+        }
+        assert_eq!(Binding::parse("Key:KEY_W"), None);
+    }
+
+    #[test]
+    fn bindings_from_json_keep_defaults_for_missing_actions() {
+        let json = serde_json::json!({ "MoveForward": ["Key:Z", "Pad:LeftStickY+", "Key:???"], "ToggleMap": [] });
+        let bindings = Bindings::from_json(&json).unwrap();
+        assert_eq!(
+            bindings.get(Action::MoveForward),
+            [Binding::Key(KeyboardKey::KEY_Z), Binding::Axis { axis: Axis::LeftStickY, positive: true }]
+        );
+        assert!(bindings.get(Action::ToggleMap).is_empty());
+        assert_eq!(bindings.get(Action::Hint), Action::Hint.default_bindings());
+
+        assert_eq!(Bindings::from_json(&bindings.to_json()).unwrap(), bindings);
+        assert!(Bindings::from_json(&serde_json::json!({ "Hint": "Key:H" })).is_err());
+    }
+
+    #[test]
+    fn add_and_remove_controls() {
+        let mut bindings = Bindings::default();
+        bindings.add(Action::Hint, Binding::Key(KeyboardKey::KEY_H));
+        assert_eq!(bindings.get(Action::Hint).len(), 1); // Already bound
+        bindings.add(Action::Hint, Binding::Button(Button::West));
+        assert_eq!(bindings.remove_last(Action::Hint), Some(Binding::Button(Button::West)));
+        assert_eq!(bindings.remove_last(Action::Hint), Some(Binding::Key(KeyboardKey::KEY_H)));
+        assert_eq!(bindings.remove_last(Action::Hint), None);
+    }
+
+    #[test]
+    fn racers_split_the_keyboard_and_keep_pad_controls() {
+        let mut bindings = Bindings::default();
+        bindings.add(Action::MoveForward, Binding::Button(Button::RightTrigger));
+        bindings.add(Action::MoveForward, Binding::Mouse(MouseButton::MOUSE_BUTTON_LEFT));
+        let (first, second) = (bindings.for_racer(&Bindings::racer_keys(0)), bindings.for_racer(&Bindings::racer_keys(1)));
+
+        assert_eq!(
+            first.get(Action::MoveForward),
+            [
+                Binding::Key(KeyboardKey::KEY_W),
+                Binding::Axis { axis: Axis::LeftStickY, positive: true },
+                Binding::Button(Button::RightTrigger),
+            ]
+        );
+        assert!(second.get(Action::TurnLeft).contains(&Binding::Key(KeyboardKey::KEY_LEFT)));
+        assert!(second.get(Action::TurnLeft).contains(&Binding::Axis { axis: Axis::RightStickX, positive: false }));
+        // No key is shared between the halves, and nothing but movement is bound
+        let keys = |bindings: &Bindings| -> Vec<Binding> {
+            let all = Action::MOVEMENT.iter().flat_map(|&action| bindings.get(action).to_vec());
+            all.filter(|binding| matches!(binding, Binding::Key(_))).collect()
+        };
+        let theirs = keys(&second);
+        assert!(keys(&first).iter().all(|key| !theirs.contains(key)));
+        assert!(first.get(Action::Back).is_empty() && second.get(Action::ToggleMap).is_empty());
+    }
+
+    #[test]
+    fn racer_keys_can_be_rebound() {
+        let json = serde_json::json!({ "MoveForward": ["Key:KP_8"], "Hint": ["Key:H"] });
+        let keys = Bindings::from_json_over(&json, Bindings::racer_keys(1)).unwrap();
+        assert_eq!(keys.get(Action::MoveForward), [Binding::Key(KeyboardKey::KEY_KP_8)]);
+        assert_eq!(keys.get(Action::TurnLeft), Bindings::racer_keys(1).get(Action::TurnLeft));
+        assert!(keys.get(Action::Hint).is_empty()); // Only movement belongs to a racer
+
+        // Pad controls on the keyboard half are ignored, the shared ones are kept
+        let mut keys = keys;
+        keys.add(Action::MoveForward, Binding::Button(Button::South));
+        let racer = Bindings::default().for_racer(&keys);
+        assert_eq!(
+            racer.get(Action::MoveForward),
+            [Binding::Key(KeyboardKey::KEY_KP_8), Binding::Axis { axis: Axis::LeftStickY, positive: true }]
+        );
+        assert_eq!(racer.key_hint(), "KP_8/K/J/L + U/O");
+        assert_eq!(Bindings::racer_keys(0).key_hint(), "W/S/A/D + Q/E");
+    }
+
+    #[test]
+    fn sticks_turn_twice_as_fast() {
+        let stick = Binding::Axis { axis: Axis::RightStickX, positive: true };
+        let trigger = Binding::Axis { axis: Axis::RightZ, positive: true };
+        assert_eq!(turn_gain(Action::TurnRight, stick), STICK_TURN_GAIN);
+        assert_eq!(turn_gain(Action::TurnRight, trigger), 1.0);
+        assert_eq!(turn_gain(Action::TurnLeft, Binding::Key(KeyboardKey::KEY_LEFT)), 1.0);
+        // Only turning; moving with a stick keeps its 0..1 range
+        assert_eq!(turn_gain(Action::MoveForward, stick), 1.0);
+    }
+
+    #[test]
+    fn default_turns_match_the_old_controls() {
+        // Before rebinding: ← and the left trigger grew the angle, → and the right trigger shrank it
+        let bindings = Bindings::default();
+        assert!(bindings.get(Action::TurnRight).contains(&Binding::Key(KeyboardKey::KEY_LEFT)));
+        assert!(bindings.get(Action::TurnRight).contains(&Binding::Axis { axis: Axis::LeftZ, positive: true }));
+        assert!(bindings.get(Action::TurnLeft).contains(&Binding::Key(KeyboardKey::KEY_RIGHT)));
+        assert!(bindings.get(Action::TurnLeft).contains(&Binding::Axis { axis: Axis::RightZ, positive: true }));
+    }
+
+    #[test]
+    fn pressed_is_only_the_first_frame() {
+        let mut input = Input::new();
+        input.set(Action::Hint, 1.0);
+        assert!(input.pressed(Action::Hint));
+        input.set(Action::Hint, 1.0);
+        assert!(input.held(Action::Hint) && !input.pressed(Action::Hint));
+        input.set(Action::Hint, 0.0);
+        assert!(!input.held(Action::Hint));
+    }
+}
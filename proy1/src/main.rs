@@ -4,45 +4,110 @@
 mod line;
 mod framebuffer;
 mod maze;
+mod maze_file;
+mod maze_gen;
+mod rng;
+mod solver;
+mod hints;
+mod npc;
+mod validate;
 mod caster;
 mod player;
 mod render;
+mod sprites;
 mod textures;
 mod game_state;
+mod headless;
 mod audio;
+mod capture;
+mod settings;
+mod input;
+mod gamepad;
+mod race;
+mod timestep;
+mod ui;
 
 use line::line;
-use maze::Maze;
+use maze::{is_goal_at, Maze, WORLD_BLOCK_SIZE};
 use caster::{cast_ray, Intersect};
-use framebuffer::Framebuffer;
-use player::{Player, process_events};
-use render::{render_2d, render_3d};
+use framebuffer::{Framebuffer, Hud};
+use player::Player;
+use render::{render_2d, render_3d, Scene};
+use sprites::SpriteManager;
 use textures::TextureManager;
 use game_state::{GameState, GameStateManager};
 use audio::AudioManager;
+use hints::Hints;
+use npc::{spawn_npcs, Npc};
+use capture::{Capture, CAPTURE_DIR, RECORD_EVERY};
+use settings::{Settings, MIN_WINDOW_HEIGHT, MIN_WINDOW_WIDTH};
+use input::{Action, Input};
+use gamepad::Gamepads;
+use race::Race;
+use timestep::{FixedTimestep, SIMULATION_HZ};
 
 use raylib::prelude::*;
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 use std::f32::consts::PI;
 use gilrs::{Gilrs, Button, Axis};
 
+// Pokémon placed in each maze
+const POKEMON_PER_LEVEL: usize = 5;
+// Times an enemy can catch the player before the level is lost
+const PLAYER_LIVES: u32 = 3;
+// Time in a level before standing on 'g' counts, so it can't be won on the very first frames
+const GOAL_GRACE: Duration = Duration::from_secs(1);
+// Rendering cap; the simulation runs at SIMULATION_HZ whatever the frame rate
+const TARGET_FPS: u32 = 144;
+
 fn main() {
-    let window_width = 800;
-    let window_height = 600;
+    // Window size, fullscreen and render scale from the user's settings.json
+    let settings = Settings::load();
+    let window_width = settings.window_width;
+    let window_height = settings.window_height;
+
+    // Headless: `--render salida.ppm [semilla]` saves one frame without opening a window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "--render") {
+        let (render_width, render_height) = settings.render_size(window_width, window_height);
+        match headless::render_cli(&args[1..], render_width, render_height, POKEMON_PER_LEVEL) {
+            Ok(path) => println!("🖼️ Frame guardado en {}", path),
+            Err(e) => {
+                println!("❌ {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
 
     let (mut rl, thread) = raylib::init()
         .size(window_width, window_height)
         .title("Pokémon Raycaster")
+        .resizable()
         .log_level(TraceLogLevel::LOG_WARNING)
         .build();
+    rl.set_window_min_size(MIN_WINDOW_WIDTH, MIN_WINDOW_HEIGHT);
+    rl.set_target_fps(TARGET_FPS);
+    // ESC is the default Back binding; raylib would otherwise close the window with it
+    rl.set_exit_key(None);
+    if settings.fullscreen {
+        rl.toggle_fullscreen();
+    }
 
     // Enable mouse for camera rotation
     rl.set_mouse_cursor(MouseCursor::MOUSE_CURSOR_CROSSHAIR);
     rl.disable_cursor(); // Hide cursor for FPS-style controls
 
-    let mut framebuffer = Framebuffer::new(window_width as u32, window_height as u32);
+    // The 3D view is rendered at render_scale of the window and stretched over it
+    let (render_width, render_height) = settings.render_size(window_width, window_height);
+    let mut framebuffer = Framebuffer::new(render_width, render_height);
     framebuffer.set_background_color(Color::new(50, 50, 100, 255));
+    // One render thread per core unless RENDER_THREADS says otherwise (1 = single-threaded)
+    if let Some(threads) = std::env::var("RENDER_THREADS").ok().and_then(|value| value.parse().ok()) {
+        framebuffer.set_render_threads(threads);
+    }
+    println!("🧵 Render 3D con {} hilo(s)", framebuffer.render_threads());
 
     // Initialize gamepad support
     let mut gilrs = Gilrs::new().unwrap_or_else(|err| {
@@ -67,30 +132,42 @@ fn main() {
     
     // Initialize game state manager first
     let mut game_state_manager = GameStateManager::new();
+    // From here on the settings live in the manager, where the settings screen edits them
+    game_state_manager.settings = settings;
     
     // Create initial maze with default size (will be recreated when starting game)
     let mut maze_obj = Maze::new(8, 6);
     let mut maze = &maze_obj.map;
     
-    // Create player at a safe starting position
-    let mut player = Player {
-        pos: Vector2::new(25.0, 25.0), // Will be reset when maze is recreated
-        a: PI / 4.0,
-        fov: PI / 3.0,
-    };
+    // Create player on the maze's 'p' cell (reset again whenever the maze is recreated)
+    let (spawn_pos, spawn_angle) = maze_obj.spawn_pose();
+    let mut player = Player::new(spawn_pos, spawn_angle, game_state_manager.settings.fov_radians());
+    let mut race = Race::new(&maze_obj, &game_state_manager.settings); // Split-screen race, restarted with each maze
 
-    let texture_manager = TextureManager::new(&mut rl, &thread);
+    let mut texture_manager = TextureManager::new(&mut rl, &thread);
+    let mut sprite_manager = SpriteManager::new(); // Pokémon billboards placed in the maze
+    let mut npcs: Vec<Npc> = Vec::new(); // Enemies patrolling the maze
+    let mut lives = PLAYER_LIVES;
+    let mut surfaces = game_state_manager.get_surface_style(); // Floor/ceiling of the current region
     let mut mode_3d = true;
     let mut mouse_enabled = true; // Track mouse control state
-    let mut frame_counter_since_playing = 0u32; // Counter for frames since entering Playing state
+    let mut time_in_level = Duration::ZERO; // Real time since entering Playing or Race, for GOAL_GRACE
+    let mut clock = FixedTimestep::new(SIMULATION_HZ); // Movement runs in fixed steps, drawing interpolates
+    let mut previous_pose = player.pose(); // Player at the previous simulation step
     let mut previous_state = GameState::Welcome; // Track previous state for transitions
     let mut last_selected_level = game_state_manager.selected_level; // Track level changes
     let mut maze_needs_recreation = false; // Flag to recreate maze when level changes
+    let mut hints = Hints::new(); // Breadcrumbs to the goal, each one costs time
+    let mut level_start = Instant::now(); // Level clock for the victory screen
+    let mut capture = Capture::new(CAPTURE_DIR, RECORD_EVERY); // F12 screenshots, F9 frame sequences
+    let mut input = Input::new(); // Actions from the bindings in settings.json, read once per frame
+    let mut gamepads = Gamepads::new(&gilrs); // gilrs events, drained once per frame into this snapshot
     
     // Inicializar sistema de audio con Taylor Swift
     let mut audio_manager = match AudioManager::new() {
-        Ok(audio) => {
+        Ok(mut audio) => {
             println!("🎵 Sistema de audio inicializado con música de Taylor Swift! ✨");
+            audio.set_volume(game_state_manager.settings.volume);
             Some(audio)
         },
         Err(e) => {
@@ -122,7 +199,7 @@ fn main() {
     }
 
     // Main game loop
-    while !rl.window_should_close() {
+    while !rl.window_should_close() && !game_state_manager.quit_requested {
         // Update FPS (update every 0.5 seconds for more responsive display)
         frame_count += 1;
         if fps_timer.elapsed().as_secs_f32() >= 0.5 {
@@ -131,8 +208,21 @@ fn main() {
             fps_timer = Instant::now();
         }
         
+        let settings = &game_state_manager.settings;
+        gamepads.update(&mut gilrs);
+        input.update(&rl, &gamepads, &settings.bindings, settings.stick_deadzone);
+        let frame_time = rl.get_frame_time();
+        let steps = clock.advance(frame_time);
+
+        // F11 toggles fullscreen at the configured resolution; windowed mode can also be resized freely
+        if input.pressed(Action::Fullscreen) {
+            rl.toggle_fullscreen();
+        }
+        let (render_width, render_height) = game_state_manager.settings.render_size(rl.get_screen_width(), rl.get_screen_height());
+        framebuffer.resize(render_width, render_height);
+
         // Update game state
-        game_state_manager.update(&mut rl, &mut gilrs);
+        game_state_manager.update(&mut rl, &gamepads, &input);
         
         // Check if level selection changed
         if last_selected_level != game_state_manager.selected_level {
@@ -140,20 +230,46 @@ fn main() {
             maze_needs_recreation = true;
             let (width, height) = game_state_manager.get_maze_size();
             let (name, desc, diff) = game_state_manager.get_level_info();
-            println!("🎯 Nivel cambiado a: {} - {} ({}x{})", name, diff, width, height);
+            let algorithm = game_state_manager.get_maze_algorithm().generator();
+            println!("🎯 Nivel cambiado a: {} - {} ({}x{}, {})", name, diff, width, height, algorithm.name());
         }
         
-        // Recreate maze if needed (when starting to play or level changed)
-        if game_state_manager.current_state == GameState::Playing &&
-            (previous_state != GameState::Playing || maze_needs_recreation) {
+        // Recreate maze if needed (when starting to play or race, or level changed)
+        let in_game = matches!(game_state_manager.current_state, GameState::Playing | GameState::Race);
+        if in_game && (previous_state != game_state_manager.current_state || maze_needs_recreation) {
             let (width, height) = game_state_manager.get_maze_size();
-            println!("🗺️ Creando nuevo laberinto {}x{}", width, height);
-            maze_obj = Maze::new(width, height);
+            let algorithm = game_state_manager.get_maze_algorithm();
+            let custom_level = game_state_manager.selected_custom_level().cloned();
+            let requested_seed = game_state_manager.take_requested_seed();
+            maze_obj = match (custom_level, requested_seed) {
+                (Some(level), _) => {
+                    println!("📁 Cargando nivel '{}'", level.meta.name.as_deref().unwrap_or("?"));
+                    level
+                },
+                (None, Some(seed)) => Maze::from_seed(width, height, algorithm, seed),
+                (None, None) => Maze::with_algorithm(width, height, algorithm),
+            };
             maze = &maze_obj.map;
+            game_state_manager.current_seed = Some(maze_obj.seed);
+            println!("🗺️ Nuevo laberinto {}x{} ({:?}), semilla {}", width, height, algorithm, maze_obj.seed);
             
             // Reset player to starting position
-            player.pos = Vector2::new(25.0, 25.0);
-            player.a = PI / 4.0;
+            player.respawn(maze_obj.spawn_pose());
+            
+            hints = Hints::new();
+            level_start = Instant::now();
+            sprite_manager.place_in_maze(&maze_obj, maze_obj.meta.pokemon.unwrap_or(POKEMON_PER_LEVEL));
+            npcs = spawn_npcs(&maze_obj, game_state_manager.get_enemy_count());
+            lives = PLAYER_LIVES;
+            race = Race::new(&maze_obj, &game_state_manager.settings);
+            game_state_manager.race_winner = None;
+            
+            texture_manager.set_wall_textures(&game_state_manager.get_wall_textures());
+            surfaces = game_state_manager.get_surface_style();
+            texture_manager.preload_image(&surfaces.floor);
+            if let Some(ceiling) = &surfaces.ceiling {
+                texture_manager.preload_image(ceiling);
+            }
             
             maze_needs_recreation = false;
             println!("✅ Laberinto recreado exitosamente");
@@ -161,17 +277,24 @@ fn main() {
         
         // Check for state transitions and reset player position when leaving Victory state
         if previous_state == GameState::Victory && game_state_manager.current_state != GameState::Victory {
-            player.pos = Vector2::new(25.0, 25.0); // Reset player to starting position
-            frame_counter_since_playing = 0; // Reset victory delay counter
+            player.respawn(maze_obj.spawn_pose()); // Reset player to starting position
+            time_in_level = Duration::ZERO; // Reset victory delay
             println!("🔄 Jugador reseteado a posición inicial tras salir de Victoria");
         }
+        // Apply what was changed on the settings screen
+        if previous_state == GameState::Settings && game_state_manager.current_state != GameState::Settings {
+            player.fov = game_state_manager.settings.fov_radians();
+            if let Some(ref mut audio) = audio_manager {
+                audio.set_volume(game_state_manager.settings.volume);
+            }
+        }
         previous_state = game_state_manager.current_state;
         
         // Actualizar sistema de audio
         if let Some(ref mut audio) = audio_manager {
             audio.update_music();
             // Handle gamepad controls for audio
-            if let Err(e) = audio.handle_gamepad_controls(&gilrs) {
+            if let Err(e) = audio.handle_gamepad_controls(&gamepads) {
                 println!("Error with gamepad audio controls: {}", e);
             }
         }
@@ -191,17 +314,32 @@ fn main() {
         }
         
         // Check if player reached goal in Playing state (with delay to prevent instant victory)
-        if game_state_manager.current_state == GameState::Playing {
-            frame_counter_since_playing += 1;
+        let mut goal_locked = false; // Standing on 'g' while the level still wants captures
+        let racing = game_state_manager.current_state == GameState::Race;
+        if game_state_manager.current_state == GameState::Playing || racing {
+            time_in_level += Duration::from_secs_f32(frame_time);
             
-            // Only check for victory after 60 frames (1 second at 60fps) to prevent instant triggers
-            if frame_counter_since_playing > 60 {
-                let world_block_size = 20.0;
-                let maze_x = (player.pos.x / world_block_size) as usize;
-                let maze_y = (player.pos.y / world_block_size) as usize;
-                
-                if maze_y < maze.len() && maze_x < maze[0].len() && maze[maze_y][maze_x] == 'g' {
+            // Only check for victory after GOAL_GRACE to prevent instant triggers
+            if time_in_level > GOAL_GRACE && racing {
+                // Split screen: the first player on 'g' wins, captures don't matter
+                if let Some(winner) = race.check_finish(maze) {
+                    println!("🏁 ¡El Jugador {} gana la carrera!", winner + 1);
+                    game_state_manager.current_state = GameState::Victory;
+                    game_state_manager.race_winner = Some(winner);
+                    game_state_manager.level_time = Some(level_start.elapsed());
+                    game_state_manager.hints_used = 0;
+                    if let Some(ref audio) = audio_manager {
+                        let _ = audio.play_victory();
+                    }
+                }
+            } else if time_in_level > GOAL_GRACE {
+                let on_goal = is_goal_at(maze, player.pos);
+                goal_locked = on_goal && maze_obj.meta.capture_all && !sprite_manager.all_captured();
+                if on_goal && !goal_locked {
                     game_state_manager.current_state = GameState::Victory;
+                    game_state_manager.level_time = Some(level_start.elapsed() + hints.penalty());
+                    game_state_manager.hints_used = hints.used;
+                    game_state_manager.pokemon_captured = (sprite_manager.captured(), sprite_manager.total());
                     // Reproducir sonido de victoria
                     if let Some(ref audio) = audio_manager {
                         let _ = audio.play_victory();
@@ -209,8 +347,8 @@ fn main() {
                 }
             }
         } else {
-            // Reset counter when not in Playing state
-            frame_counter_since_playing = 0;
+            // Reset the delay when not in Playing or Race state
+            time_in_level = Duration::ZERO;
         }
         
         match game_state_manager.current_state {
@@ -222,39 +360,47 @@ fn main() {
                 let mut d = rl.begin_drawing(&thread);
                 game_state_manager.draw_level_select(&mut d);
             },
+            GameState::Settings => {
+                let mut d = rl.begin_drawing(&thread);
+                game_state_manager.draw_settings(&mut d);
+            },
+            GameState::Controls => {
+                let mut d = rl.begin_drawing(&thread);
+                game_state_manager.draw_bindings(&mut d);
+            },
             GameState::Playing => {
                 // Controles de audio de Taylor Swift
-                if rl.is_key_pressed(KeyboardKey::KEY_N) {
+                if input.pressed(Action::NextTrack) {
                     if let Some(ref mut audio) = audio_manager {
                         if let Err(e) = audio.next_track() {
                             println!("Error cambiando canción: {}", e);
                         }
                     }
                 }
-                if rl.is_key_pressed(KeyboardKey::KEY_P) {
+                if input.pressed(Action::PreviousTrack) {
                     if let Some(ref mut audio) = audio_manager {
                         if let Err(e) = audio.previous_track() {
                             println!("Error cambiando canción: {}", e);
                         }
                     }
                 }
-                if rl.is_key_pressed(KeyboardKey::KEY_SPACE) {
+                if input.pressed(Action::ToggleAudio) {
                     if let Some(ref mut audio) = audio_manager {
                         audio.toggle_audio();
                     }
                 }
                 
                 // TEST GAMEPAD - Presiona G para testear gamepad
-                if rl.is_key_pressed(KeyboardKey::KEY_G) {
+                if input.pressed(Action::GamepadTest) {
                     println!("\n🎮 === GAMEPAD TEST ===");
-                    let gamepad_count = gilrs.gamepads().count();
+                    let gamepad_count = gamepads.count();
                     
                     if gamepad_count == 0 {
                         println!("❌ No gamepads detected");
                     } else {
-                        for (id, gamepad) in gilrs.gamepads() {
-                            println!("🎮 Gamepad: {} (ID: {})", gamepad.name(), id);
-                            println!("   Connected: {}", gamepad.is_connected());
+                        for (id, pad) in gamepads.pads() {
+                            println!("🎮 Gamepad: {} (ID: {})", pad.name, id);
+                            println!("   Left stick: ({:.2}, {:.2})", pad.value(Axis::LeftStickX), pad.value(Axis::LeftStickY));
                         }
                     }
                     println!("===================\n");
@@ -265,7 +411,34 @@ fn main() {
 
                 // 2. move the player on user input
                 let old_pos = player.pos;
-                process_events(&mut player, &rl, &maze, &mut gilrs);
+                // Mouse look once per frame and not interpolated; keys and sticks in fixed steps
+                if rl.is_cursor_hidden() {
+                    let before = player.a;
+                    player.update_mouse(&rl, &game_state_manager.settings);
+                    previous_pose.a += player.a - before;
+                }
+                for _ in 0..steps {
+                    previous_pose = player.pose();
+                    player.update_actions(&input, maze, &game_state_manager.settings, clock.step());
+
+                    // Enemigos: patrullan, persiguen al jugador si lo ven y lo mandan al inicio si lo tocan
+                    for npc in &mut npcs {
+                        npc.update(maze, player.pos, clock.step());
+                    }
+                    if npcs.iter().any(|npc| npc.touches(player.pos)) {
+                        lives -= 1;
+                        player.respawn(maze_obj.spawn_pose());
+                        previous_pose = player.pose();
+                        npcs.iter_mut().for_each(Npc::send_home);
+                        if lives == 0 {
+                            println!("💀 ¡Sin vidas! Elige una región para intentarlo de nuevo");
+                            game_state_manager.current_state = GameState::LevelSelect;
+                        } else {
+                            println!("👻 ¡Un Gastly te atrapó! Vuelves al inicio ({} vidas)", lives);
+                        }
+                        break;
+                    }
+                }
                 
                 // Reproducir sonido de pasos si el jugador se movió
                 if old_pos != player.pos {
@@ -274,13 +447,48 @@ fn main() {
                     }
                 }
 
+                // Guardar el laberinto actual en levels/ para poder editarlo o compartirlo
+                if input.pressed(Action::SaveLevel) {
+                    let mut level = maze_obj.clone();
+                    let (name, _, _) = game_state_manager.get_level_info();
+                    level.meta.name.get_or_insert_with(|| format!("{} #{}", name, maze_obj.seed));
+                    let path = maze_file::levels_dir().join(format!("nivel_{}.txt", maze_obj.seed));
+                    match level.save(&path) {
+                        Ok(()) => {
+                            println!("💾 Laberinto guardado en {:?}", path);
+                            game_state_manager.load_custom_levels();
+                        },
+                        Err(e) => println!("⚠️ Error guardando laberinto: {}", e),
+                    }
+                }
+
+                // Pista: muestra el camino hacia la meta por unos segundos a cambio de tiempo
+                if input.pressed(Action::Hint) && hints.request() {
+                    println!("💡 Pista #{} (+{}s)", hints.used, hints::HINT_PENALTY.as_secs());
+                }
+                hints.update(maze, player.pos, WORLD_BLOCK_SIZE as f32, Duration::from_secs_f32(rl.get_frame_time()));
+                sprite_manager.update();
+                for name in sprite_manager.capture_at(player.pos) {
+                    println!("🎉 ¡{} capturado! ({}/{})", name, sprite_manager.captured(), sprite_manager.total());
+                }
+
                 // 3. toggle between 2D and 3D mode
-                if rl.is_key_pressed(KeyboardKey::KEY_M) {
+                if input.pressed(Action::ToggleMap) {
                     mode_3d = !mode_3d;
                 }
 
+                // F12: screenshot of this frame; F9: start/stop saving every RECORD_EVERY-th frame
+                if input.pressed(Action::Screenshot) {
+                    capture.request_screenshot();
+                }
+                if input.pressed(Action::Record)
+                    && let Some(dir) = capture.toggle_recording(SystemTime::now())
+                {
+                    println!("⏺️ Grabando 1 de cada {} frames en {}", RECORD_EVERY, dir.display());
+                }
+
                 // Toggle mouse control with C key
-                if rl.is_key_pressed(KeyboardKey::KEY_C) {
+                if input.pressed(Action::ToggleMouse) {
                     mouse_enabled = !mouse_enabled;
                     if mouse_enabled {
                         rl.disable_cursor();
@@ -290,32 +498,108 @@ fn main() {
                 }
                 
                 // Back to menu with ESC
-                if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+                if input.pressed(Action::Back) {
                     game_state_manager.current_state = GameState::Welcome;
                 }
 
-                // 4. draw stuff
+                // 4. draw stuff, between the last two simulation steps
+                let view = player.interpolated(previous_pose, clock.alpha());
                 if mode_3d {
-                    render_3d(&mut framebuffer, &player, &maze, &texture_manager);
+                    let scene = Scene {
+                        surfaces: &surfaces,
+                        breadcrumbs: hints.breadcrumbs(),
+                        sprites: &sprite_manager,
+                        npcs: &npcs,
+                    };
+                    render_3d(&mut framebuffer, &view, &maze, &texture_manager, &scene);
                 } else {
-                    render_2d(&mut framebuffer, &player, &maze);
+                    render_2d(&mut framebuffer, &view, &maze);
                 }
 
                 // 5. draw framebuffer content with FPS and minimap
                 let track_info = audio_manager.as_ref().map(|audio| audio.get_current_track_info());
-                framebuffer.swap_buffers_with_fps_and_minimap(
+                let hud = Hud {
+                    fps: current_fps,
+                    track_info: track_info.as_deref(),
+                    breadcrumbs: hints.breadcrumbs(),
+                    captured: sprite_manager.captured(),
+                    total_pokemon: sprite_manager.total(),
+                    goal_locked,
+                    lives,
+                    npcs: &npcs,
+                    recording: capture.is_recording(),
+                    key_hint: "",
+                };
+                let captures = capture.frame_targets(SystemTime::now());
+                let frame = framebuffer.swap_buffers_with_fps_and_minimap(
                     &mut rl, 
                     &thread, 
-                    current_fps, 
-                    &player, 
+                    &view, 
                     &maze,
-                    track_info.as_deref()
+                    &hud,
+                    !captures.is_empty(),
                 );
+                if let Some(frame) = frame {
+                    capture.save(frame, captures);
+                }
+
+            },
+            GameState::Race => {
+                // Shared controls (audio, captures, back to menu) come from the usual bindings;
+                // each player moves with their own keyboard half and gamepad
+                if input.pressed(Action::ToggleAudio) && let Some(ref mut audio) = audio_manager {
+                    audio.toggle_audio();
+                }
+                if input.pressed(Action::Screenshot) {
+                    capture.request_screenshot();
+                }
+                if input.pressed(Action::Record)
+                    && let Some(dir) = capture.toggle_recording(SystemTime::now())
+                {
+                    println!("⏺️ Grabando 1 de cada {} frames en {}", RECORD_EVERY, dir.display());
+                }
+                if input.pressed(Action::Back) {
+                    game_state_manager.current_state = GameState::Welcome;
+                }
+
+                framebuffer.clear();
+                race.read_input(&rl, &gamepads, game_state_manager.settings.stick_deadzone);
+                for _ in 0..steps {
+                    race.step(maze, &game_state_manager.settings, clock.step());
+                }
+                sprite_manager.update();
+
+                let scene = Scene {
+                    surfaces: &surfaces,
+                    breadcrumbs: &[],
+                    sprites: &sprite_manager,
+                    npcs: &[],
+                };
+                race.render(&mut framebuffer, maze, &texture_manager, &scene, clock.alpha());
+
+                let track_info = audio_manager.as_ref().map(|audio| audio.get_current_track_info());
+                let key_hint = race.key_hint();
+                let hud = Hud {
+                    fps: current_fps,
+                    track_info: track_info.as_deref(),
+                    breadcrumbs: &[],
+                    captured: 0,
+                    total_pokemon: 0,
+                    goal_locked: false,
+                    lives: 0,
+                    npcs: &[],
+                    recording: capture.is_recording(),
+                    key_hint: &key_hint,
+                };
+                let captures = capture.frame_targets(SystemTime::now());
+                if let Some(frame) = framebuffer.swap_buffers_split(&mut rl, &thread, &race.players, maze, &hud, !captures.is_empty()) {
+                    capture.save(frame, captures);
+                }
             },
             GameState::Victory => {
                 // Check input first (before begin_drawing)
-                let should_exit = rl.is_key_pressed(KeyboardKey::KEY_ESCAPE);
-                let should_restart = rl.is_key_pressed(KeyboardKey::KEY_R);
+                let should_exit = input.pressed(Action::Back);
+                let should_restart = input.pressed(Action::Restart);
                 
                 let mut d = rl.begin_drawing(&thread);
                 game_state_manager.draw_victory(&mut d);
@@ -326,11 +610,9 @@ fn main() {
                 }
                 if should_restart {
                     // Player position will be reset automatically by transition detection above
-                    game_state_manager.current_state = GameState::Playing;
+                    game_state_manager.current_state = game_state_manager.replay_state();
                 }
             }
         }
-
-        thread::sleep(Duration::from_millis(16));
     }
 }
\ No newline at end of file
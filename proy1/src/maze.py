@@ -1,5 +1,5 @@
 import sys
-from random import shuffle, randrange
+from random import shuffle, randrange, seed
 
 def make_maze(w = 16, h = 8):
     vis = [[0] * w + [1] for _ in range(h)] + [[1] * (w + 1)]
@@ -43,4 +43,6 @@ def make_maze(w = 16, h = 8):
         return json.dumps(jsona)
 
 if __name__ == '__main__': 
+    if len(sys.argv) > 4:
+        seed(int(sys.argv[4]))
     print(make_maze(int(sys.argv[2]), int(sys.argv[3])))
\ No newline at end of file
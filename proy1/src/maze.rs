@@ -1,28 +1,186 @@
+use std::f32::consts::PI;
+use std::path::Path;
+
+use raylib::prelude::Vector2;
+
+use crate::maze_file::{self, LevelMeta};
+use crate::maze_gen::{self, CellGrid};
+use crate::rng::Rng;
+use crate::validate::{self, MazeError};
+
+// Side of one map char in world units; movement, rays and every view convert through this
+pub const WORLD_BLOCK_SIZE: usize = 20;
+
+// Special walls: they block like any other wall but get their own texture
+pub const MOSSY_WALL: char = 'M';
+pub const BRICK_WALL: char = 'B';
+pub const GOAL_DOOR: char = 'D';
+
+// Characters that block movement and rays
+pub fn is_wall_cell(cell: char) -> bool {
+    matches!(cell, '+' | '-' | '|' | MOSSY_WALL | BRICK_WALL | GOAL_DOOR)
+}
+
+// Whether a world position stands on the goal cell 'g'
+pub fn is_goal_at(maze: &[Vec<char>], pos: Vector2) -> bool {
+    let block = WORLD_BLOCK_SIZE as f32;
+    if pos.x < 0.0 || pos.y < 0.0 {
+        return false;
+    }
+    let (x, y) = ((pos.x / block) as usize, (pos.y / block) as usize);
+    maze.get(y).and_then(|row| row.get(x)) == Some(&'g')
+}
+
+/// Algoritmo que talla pasillos sobre una cuadrícula con todas las paredes en pie.
+pub trait MazeGenerator {
+    fn name(&self) -> &'static str;
+    fn carve(&self, grid: &mut CellGrid, rng: &mut Rng);
+}
+
+// Selectable generators; each gives the corridors a different character
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MazeAlgorithm {
+    RecursiveBacktracker,
+    Prim,
+    Kruskal,
+    Eller,
+    Wilson,
+    BinaryTree,
+    RecursiveDivision,
+}
+
+impl MazeAlgorithm {
+    pub const ALL: [MazeAlgorithm; 7] = [
+        MazeAlgorithm::RecursiveBacktracker,
+        MazeAlgorithm::Prim,
+        MazeAlgorithm::Kruskal,
+        MazeAlgorithm::Eller,
+        MazeAlgorithm::Wilson,
+        MazeAlgorithm::BinaryTree,
+        MazeAlgorithm::RecursiveDivision,
+    ];
+
+    pub fn generator(self) -> Box<dyn MazeGenerator> {
+        match self {
+            MazeAlgorithm::RecursiveBacktracker => Box::new(maze_gen::RecursiveBacktracker),
+            MazeAlgorithm::Prim => Box::new(maze_gen::Prim),
+            MazeAlgorithm::Kruskal => Box::new(maze_gen::Kruskal),
+            MazeAlgorithm::Eller => Box::new(maze_gen::Eller),
+            MazeAlgorithm::Wilson => Box::new(maze_gen::Wilson),
+            MazeAlgorithm::BinaryTree => Box::new(maze_gen::BinaryTree),
+            MazeAlgorithm::RecursiveDivision => Box::new(maze_gen::RecursiveDivision),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Maze {
     pub map: Vec<Vec<char>>,
     pub width: usize,
     pub height: usize,
+    pub seed: u64, // Same seed + size + algorithm always rebuilds this exact map
+    pub meta: LevelMeta, // Name, spawn angle and textures from a level file (empty when generated)
+}
+
+// Where new mazes come from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeneratorBackend {
+    Native,   // maze_gen.rs, no external dependencies
+    External, // src/maze.py through a Python interpreter
+}
+
+impl GeneratorBackend {
+    // MAZE_GENERATOR=python opts into the external script; anything else uses the native one
+    pub fn from_env() -> Self {
+        match std::env::var("MAZE_GENERATOR") {
+            Ok(value) if value.eq_ignore_ascii_case("python") => GeneratorBackend::External,
+            _ => GeneratorBackend::Native,
+        }
+    }
 }
 
 impl Maze {
     pub fn new(width: usize, height: usize) -> Self {
-        println!("DEBUG: Intentando generar laberinto con Python {}x{}", width, height);
-        
-        // Primero intentar con el script de Python
-        match Self::generate_with_python(width, height) {
-            Ok(maze) => {
-                println!("DEBUG: Laberinto generado exitosamente con Python");
-                maze
-            },
-            Err(e) => {
-                println!("WARNING: No se pudo generar con Python: {}", e);
-                println!("DEBUG: Usando laberinto hardcodeado como fallback");
-                Self::create_fallback_maze(width, height)
+        Self::with_algorithm(width, height, MazeAlgorithm::RecursiveBacktracker)
+    }
+
+    // Fresh random level; the seed is kept so it can be shown and replayed later
+    pub fn with_algorithm(width: usize, height: usize, algorithm: MazeAlgorithm) -> Self {
+        let seed = Rng::from_time().next_u64();
+        Self::from_seed(width, height, algorithm, seed)
+    }
+
+    pub fn from_seed(width: usize, height: usize, algorithm: MazeAlgorithm, seed: u64) -> Self {
+        Self::with_backend(width, height, algorithm, seed, GeneratorBackend::from_env())
+    }
+
+    // The external script only knows the recursive backtracker, so `algorithm` applies to the native path.
+    // Python seeds its own RNG, so a seed only reproduces the maze within the same backend.
+    pub fn with_backend(width: usize, height: usize, algorithm: MazeAlgorithm, seed: u64, backend: GeneratorBackend) -> Self {
+        if backend == GeneratorBackend::External {
+            println!("DEBUG: Intentando generar laberinto con Python {}x{} (semilla {})", width, height, seed);
+            match Self::generate_with_python(width, height, seed) {
+                Ok(mut maze) => {
+                    maze.seed = seed;
+                    maze.mark_goal_door();
+                    println!("DEBUG: Laberinto generado exitosamente con Python");
+                    return maze;
+                },
+                Err(e) => {
+                    println!("WARNING: No se pudo generar con Python: {}", e);
+                    println!("DEBUG: Usando el generador nativo como fallback");
+                }
             }
         }
+
+        let mut maze = Self::generate(width, height, algorithm.generator().as_ref(), seed);
+        maze.mark_goal_door();
+        maze
     }
-    
-    fn generate_with_python(width: usize, height: usize) -> Result<Self, String> {
+
+    // Native generation; the same generator and seed always yield the same map on every platform
+    pub fn generate(width: usize, height: usize, generator: &dyn MazeGenerator, seed: u64) -> Self {
+        let map = maze_gen::generate_map(generator, width, height, &mut Rng::new(seed));
+        let mut maze = Maze {
+            width: map[0].len(),
+            height: map.len(),
+            map,
+            seed,
+            meta: LevelMeta::default(),
+        };
+
+        // Generators always produce perfect mazes; this only guards against a regression
+        let found = maze.repair();
+        if !found.is_empty() {
+            println!("⚠️ Laberinto '{}' reparado: {}", generator.name(), validate::describe(&found));
+        }
+        maze
+    }
+
+    // Every problem that would make the level unplayable (see validate.rs)
+    pub fn validate(&self) -> Result<(), Vec<MazeError>> {
+        let errors = validate::validate(&self.map);
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    // Fix the grid in place where possible; returns the problems it had
+    pub fn repair(&mut self) -> Vec<MazeError> {
+        let found = validate::repair(&mut self.map);
+        self.width = self.map.iter().map(|row| row.len()).max().unwrap_or(0);
+        self.height = self.map.len();
+        found
+    }
+
+    // Level file on disk; `.json` uses the JSON variant, anything else the text format
+    pub fn load(path: &Path) -> Result<Self, String> {
+        maze_file::load(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        maze_file::save(self, path)
+    }
+
+    fn generate_with_python(width: usize, height: usize, seed: u64) -> Result<Self, String> {
         // Detectar el sistema operativo para usar el comando Python correcto
         let python_commands = if cfg!(windows) {
             vec!["python", "python3", "py"]  // En Windows, intentar python, python3, y py
@@ -30,24 +188,26 @@ impl Maze {
             vec!["python3", "python"]  // En Mac/Linux, intentar python3 primero, luego python
         };
         
+        // Buscar el script junto al crate primero, y luego relativo al directorio actual
+        let manifest_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         let current_dir = std::env::current_dir()
             .map_err(|e| format!("No se pudo obtener directorio actual: {}", e))?;
         
-        let maze_py_path = current_dir.join("src").join("maze.py");
-        
-        if !maze_py_path.exists() {
-            return Err(format!("El archivo maze.py no existe en: {:?}", maze_py_path));
-        }
+        let maze_py_path = [manifest_dir.join("src").join("maze.py"), current_dir.join("src").join("maze.py")]
+            .into_iter()
+            .find(|path| path.exists())
+            .ok_or_else(|| format!("El archivo maze.py no existe en: {:?} ni en {:?}", manifest_dir, current_dir))?;
         
         // Intentar cada comando Python hasta que uno funcione
         for python_cmd in python_commands {
-            println!("DEBUG: Intentando comando: {} src/maze.py json {} {}", python_cmd, width, height);
+            println!("DEBUG: Intentando comando: {} src/maze.py json {} {} {}", python_cmd, width, height, seed);
             
             let result = std::process::Command::new(python_cmd)
                 .arg(&maze_py_path)
                 .arg("json")
                 .arg(width.to_string())
                 .arg(height.to_string())
+                .arg(seed.to_string())
                 .current_dir(&current_dir)
                 .stdout(std::process::Stdio::piped())
                 .stderr(std::process::Stdio::piped())
@@ -103,33 +263,29 @@ impl Maze {
             println!("DEBUG: JSON content preview: {}", json_str);
         }
         
-        let parsed: Vec<Vec<String>> = serde_json::from_str(json_str)
-            .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-        
-        // Convertir String a char
-        let map: Vec<Vec<char>> = parsed
-            .into_iter()
-            .map(|row| row.into_iter().map(|s| s.chars().next().unwrap_or(' ')).collect())
-            .collect();
+        let maze = maze_file::parse_json(json_str)?;
+        maze.validate()
+            .map_err(|errors| format!("Laberinto inválido: {}", validate::describe(&errors)))?;
         
-        println!("DEBUG: Laberinto convertido exitosamente, tamaño: {}x{}", map.len(), map.get(0).map_or(0, |row| row.len()));
+        println!("DEBUG: Laberinto convertido exitosamente, tamaño: {}x{}", maze.map.len(), maze.width);
         
-        Ok(Maze {
-            width: map[0].len(),
-            height: map.len(),
-            map,
-        })
+        Ok(maze)
     }
     
     fn create_fallback_maze(width: usize, height: usize) -> Self {
         // Fallback: crear un laberinto simple hardcodeado
         let map = Self::create_simple_maze(width, height);
         
-        Maze {
+        let mut maze = Maze {
             width: map[0].len(),
             height: map.len(),
             map,
-        }
+            seed: 0,
+            meta: LevelMeta::default(),
+        };
+        // Los pilares internos pueden aislar la meta; moverla si hace falta
+        maze.repair();
+        maze
     }
     
     fn create_simple_maze(width: usize, height: usize) -> Vec<Vec<char>> {
@@ -175,8 +331,7 @@ impl Maze {
     }
     
     pub fn is_wall(&self, x: usize, y: usize) -> bool {
-        let cell = self.get_cell(x, y);
-        cell == '+' || cell == '-' || cell == '|'
+        is_wall_cell(self.get_cell(x, y))
     }
     
     pub fn find_player_start(&self) -> Option<(usize, usize)> {
@@ -190,6 +345,46 @@ impl Maze {
         None
     }
     
+    /// Posición y ángulo iniciales: centro de la celda 'p', mirando a su primer vecino libre.
+    ///
+    /// Los vecinos se prueban en orden este, sur, oeste, norte. Un `spawn_angle` en el
+    /// archivo del nivel tiene prioridad. Sin 'p' se usa la celda (1, 1).
+    pub fn spawn_pose(&self) -> (Vector2, f32) {
+        let (x, y) = self.find_player_start().unwrap_or((1, 1));
+        let block = WORLD_BLOCK_SIZE as f32;
+        let pos = Vector2::new((x as f32 + 0.5) * block, (y as f32 + 0.5) * block);
+
+        let angle = self.meta.spawn_angle.unwrap_or_else(|| {
+            let facings = [(1, 0, 0.0), (0, 1, PI / 2.0), (-1, 0, PI), (0, -1, -PI / 2.0)];
+            facings
+                .iter()
+                .find(|&&(dx, dy, _)| {
+                    let nx = x as i64 + dx;
+                    let ny = y as i64 + dy;
+                    nx >= 0 && ny >= 0 && !self.is_wall(nx as usize, ny as usize)
+                })
+                .map_or(0.0, |&(_, _, angle)| angle)
+        });
+
+        (pos, angle)
+    }
+
+    // The outer wall next to 'g' becomes a door so the exit stands out down the corridor
+    pub fn mark_goal_door(&mut self) {
+        let Some((x, y)) = self.find_goal() else { return };
+        let last_row = self.map.len() - 1;
+        for (nx, ny) in [(x + 1, y), (x, y + 1), (x.wrapping_sub(1), y), (x, y.wrapping_sub(1))] {
+            let on_border = nx == 0 || ny == 0 || ny == last_row || nx + 1 == self.map[ny.min(last_row)].len();
+            if let Some(cell) = self.map.get_mut(ny).and_then(|row| row.get_mut(nx))
+                && on_border
+                && matches!(*cell, '-' | '|')
+            {
+                *cell = GOAL_DOOR;
+                return;
+            }
+        }
+    }
+
     pub fn find_goal(&self) -> Option<(usize, usize)> {
         for (y, row) in self.map.iter().enumerate() {
             for (x, &cell) in row.iter().enumerate() {
@@ -200,4 +395,49 @@ impl Maze {
         }
         None
     }
+
+    /// Laberinto con exactamente estas filas, sin validar, para las pruebas.
+    #[cfg(test)]
+    pub fn from_rows(rows: &[&str]) -> Self {
+        let map: Vec<Vec<char>> = rows.iter().map(|row| row.chars().collect()).collect();
+        Maze { width: map[0].len(), height: map.len(), map, seed: 0, meta: LevelMeta::default() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawns_at_center_of_p_facing_open_neighbor() {
+        let level = Maze::from_rows(&["+--+--+", "|  |  |", "+  +  +", "|p |g |", "+--+--+"]);
+        let (pos, angle) = level.spawn_pose();
+        assert_eq!(pos, Vector2::new(30.0, 70.0));
+        // East is the first open neighbor of (1, 3)
+        assert_eq!(angle, 0.0);
+
+        let corner = Maze::from_rows(&["+---+", "| | |", "|p|g|", "+---+"]);
+        assert_eq!(corner.spawn_pose().1, -PI / 2.0); // Only north is open
+    }
+
+    #[test]
+    fn goal_door_replaces_outer_wall_only() {
+        let mut level = Maze::from_rows(&["+--+--+", "|p    |", "+--+  +", "|    g|", "+--+--+"]);
+        level.mark_goal_door();
+        assert_eq!(level.map[3][6], GOAL_DOOR);
+        assert!(level.validate().is_ok());
+
+        // Goal away from the border: nothing to turn into a door
+        let mut inner = Maze::from_rows(&["+-----+", "|p    |", "|  g  |", "|     |", "+-----+"]);
+        let before = inner.map.clone();
+        inner.mark_goal_door();
+        assert_eq!(inner.map, before);
+    }
+
+    #[test]
+    fn level_spawn_angle_wins() {
+        let mut level = Maze::from_rows(&["+---+", "|p g|", "+---+"]);
+        level.meta.spawn_angle = Some(PI);
+        assert_eq!(level.spawn_pose().1, PI);
+    }
 }
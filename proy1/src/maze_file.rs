@@ -0,0 +1,482 @@
+//! Formato de archivo de niveles.
+//!
+//! Texto (`.txt` o cualquier extensión que no sea `.json`): una cabecera de metadatos
+//! `clave: valor`, una línea `---` y después el laberinto con los mismos caracteres ASCII
+//! que produce `maze.py`:
+//!
+//! ```text
+//! # Comentarios con '#'
+//! name: Ruta 1
+//! seed: 1234
+//! spawn_angle: 90
+//! texture |: wall1.jpg
+//! texture -: wall2.jpg
+//! floor: wall3.jpg
+//! ceiling: sky
+//! pokemon: 6
+//! capture_all: true
+//! enemies: 2
+//! ---
+//! +--+--+
+//! |p    |
+//! +--+  +
+//! |    g|
+//! +--+--+
+//! ```
+//!
+//! Todas las claves son opcionales. `spawn_angle` va en grados (0 = este, 90 = sur).
+//! Las paredes especiales `M` (musgo), `B` (ladrillo) y `D` (puerta de salida) bloquean igual que
+//! `+`, `-` y `|`. Las texturas se buscan en `assets/img/`, salvo `brick`, `mossy` y `goal_door`,
+//! que se generan en código; `floor`/`ceiling` reemplazan las de la región
+//! y `ceiling: sky` deja el cielo abierto. `pokemon` fija cuántos Pokémon se colocan y
+//! `capture_all: true` exige capturarlos todos antes de que la meta cuente; `enemies` fija
+//! cuántos enemigos patrullan el nivel. Un archivo sin línea `---` se lee
+//! completo como laberinto, así que la salida de `maze.py text` se carga tal cual.
+//!
+//! JSON: o bien la lista de filas que acepta `parse_json_maze` (`[["+", "-", ...], ...]`),
+//! o un objeto con los mismos metadatos y esa lista en `map`:
+//! `{"name": "Ruta 1", "seed": 1234, "spawn_angle": 90, "textures": {"|": "wall1.jpg"}, "floor": "wall3.jpg", "ceiling": "sky", "pokemon": 6, "capture_all": true, "enemies": 2, "map": [[...]]}`
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::maze::Maze;
+use crate::textures::SurfaceStyle;
+use crate::validate;
+
+// Hand-designed levels shown on the level-select screen; see `levels_dir`
+pub const LEVELS_DIR: &str = "levels";
+
+const HEADER_END: &str = "---";
+
+// `ceiling` value that leaves the sky open
+pub const OPEN_SKY: &str = "sky";
+
+// Everything a level file can say about itself besides the grid
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LevelMeta {
+    pub name: Option<String>,
+    pub spawn_angle: Option<f32>, // Radians
+    pub wall_textures: BTreeMap<char, String>, // Wall char -> file in assets/img
+    pub floor: Option<String>,
+    pub ceiling: Option<String>, // OPEN_SKY or a file in assets/img
+    pub pokemon: Option<usize>, // Collectibles to place; None uses the game default
+    pub capture_all: bool, // The goal only counts once every Pokémon is captured
+    pub enemies: Option<usize>, // Wandering NPCs; None uses the region default
+}
+
+impl LevelMeta {
+    // The region's floor and ceiling with whatever this level overrides
+    pub fn surface_style(&self, region: SurfaceStyle) -> SurfaceStyle {
+        SurfaceStyle {
+            floor: self.floor.clone().unwrap_or(region.floor),
+            ceiling: match self.ceiling.as_deref() {
+                Some(OPEN_SKY) => None,
+                Some(name) => Some(name.to_string()),
+                None => region.ceiling,
+            },
+        }
+    }
+}
+
+pub fn load(path: &Path) -> Result<Maze, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("No se pudo leer {:?}: {}", path, e))?;
+
+    let mut maze = if is_json(path) { parse_json(&contents)? } else { parse_text(&contents)? };
+
+    // Hand-made levels are refused rather than silently rewritten
+    maze.validate()
+        .map_err(|errors| format!("Nivel inválido: {}", validate::describe(&errors)))?;
+
+    // Levels without a name are listed by file name
+    if maze.meta.name.is_none() {
+        maze.meta.name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned());
+    }
+    Ok(maze)
+}
+
+pub fn save(maze: &Maze, path: &Path) -> Result<(), String> {
+    let contents = if is_json(path) { to_json(maze) } else { to_text(maze) };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("No se pudo crear {:?}: {}", parent, e))?;
+    }
+    fs::write(path, contents).map_err(|e| format!("No se pudo escribir {:?}: {}", path, e))
+}
+
+/// Carpeta de niveles: `levels/` junto al crate o junto al ejecutable, la primera que exista, para
+/// no depender del directorio desde el que se lanza el juego. Si no hay ninguna se usa la del
+/// directorio actual, que se crea al guardar el primer nivel.
+pub fn levels_dir() -> PathBuf {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(LEVELS_DIR);
+    let exe_dir = std::env::current_exe().ok().and_then(|exe| exe.parent().map(|dir| dir.join(LEVELS_DIR)));
+    let current_dir = PathBuf::from(LEVELS_DIR);
+    [Some(manifest_dir), exe_dir]
+        .into_iter()
+        .flatten()
+        .find(|dir| dir.is_dir())
+        .unwrap_or(current_dir)
+}
+
+// Level files in `dir`, sorted by name so the level-select order is stable
+pub fn list_levels(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.is_file()
+                    && path.extension().is_some_and(|ext| ext == "txt" || ext == "json")
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+    paths
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+pub fn parse_text(contents: &str) -> Result<Maze, String> {
+    let lines: Vec<&str> = contents.lines().map(|line| line.trim_end_matches('\r')).collect();
+
+    let (header, rows) = match lines.iter().position(|line| line.trim() == HEADER_END) {
+        Some(end) => (&lines[..end], &lines[end + 1..]),
+        None => (&lines[..0], &lines[..]),
+    };
+
+    let mut meta = LevelMeta::default();
+    let mut seed = 0;
+
+    for (number, line) in header.iter().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once(':')
+            .ok_or_else(|| format!("Línea {}: se esperaba 'clave: valor'", number + 1))?;
+        let (key, value) = (key.trim(), value.trim());
+
+        // Exactly `texture <char>`; other keys that merely start with "texture" are not textures
+        let texture_wall = if key == "texture" { Some("") } else { key.strip_prefix("texture ") };
+        if let Some(wall) = texture_wall {
+            let wall = single_char(wall.trim())
+                .ok_or_else(|| format!("Línea {}: 'texture' necesita un único carácter", number + 1))?;
+            meta.wall_textures.insert(wall, value.to_string());
+            continue;
+        }
+
+        match key {
+            "name" => meta.name = Some(value.to_string()),
+            "floor" => meta.floor = Some(value.to_string()),
+            "ceiling" => meta.ceiling = Some(value.to_string()),
+            "pokemon" => {
+                let count = value
+                    .parse()
+                    .map_err(|_| format!("Línea {}: número de Pokémon inválido '{}'", number + 1, value))?;
+                meta.pokemon = Some(count);
+            }
+            "enemies" => {
+                let count = value
+                    .parse()
+                    .map_err(|_| format!("Línea {}: número de enemigos inválido '{}'", number + 1, value))?;
+                meta.enemies = Some(count);
+            }
+            "capture_all" => {
+                meta.capture_all = value
+                    .parse()
+                    .map_err(|_| format!("Línea {}: se esperaba true o false en '{}'", number + 1, value))?;
+            }
+            "seed" => {
+                seed = value
+                    .parse()
+                    .map_err(|_| format!("Línea {}: semilla inválida '{}'", number + 1, value))?;
+            }
+            "spawn_angle" => {
+                let degrees: f32 = value
+                    .parse()
+                    .map_err(|_| format!("Línea {}: ángulo inválido '{}'", number + 1, value))?;
+                meta.spawn_angle = Some(degrees.to_radians());
+            }
+            _ => println!("⚠️ Clave de nivel desconocida ignorada: {}", key),
+        }
+    }
+
+    // Trailing blank lines are not part of the grid
+    let last_row = rows.iter().rposition(|row| !row.trim().is_empty()).map_or(0, |i| i + 1);
+    let map: Vec<Vec<char>> = rows[..last_row].iter().map(|row| row.chars().collect()).collect();
+
+    build_maze(map, seed, meta)
+}
+
+pub fn parse_json(contents: &str) -> Result<Maze, String> {
+    let value: Value = serde_json::from_str(contents)
+        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+
+    match value {
+        Value::Array(_) => build_maze(json_map(&value)?, 0, LevelMeta::default()),
+        Value::Object(ref fields) => {
+            let map = json_map(fields.get("map").ok_or("Falta el campo 'map'")?)?;
+
+            let mut meta = LevelMeta {
+                name: fields.get("name").and_then(Value::as_str).map(str::to_string),
+                floor: fields.get("floor").and_then(Value::as_str).map(str::to_string),
+                ceiling: fields.get("ceiling").and_then(Value::as_str).map(str::to_string),
+                spawn_angle: fields
+                    .get("spawn_angle")
+                    .and_then(Value::as_f64)
+                    .map(|degrees| (degrees as f32).to_radians()),
+                pokemon: fields.get("pokemon").and_then(Value::as_u64).map(|count| count as usize),
+                capture_all: fields.get("capture_all").and_then(Value::as_bool).unwrap_or(false),
+                enemies: fields.get("enemies").and_then(Value::as_u64).map(|count| count as usize),
+                ..LevelMeta::default()
+            };
+
+            if let Some(textures) = fields.get("textures").and_then(Value::as_object) {
+                for (wall, file) in textures {
+                    let wall = single_char(wall)
+                        .ok_or_else(|| format!("Textura con clave inválida '{}'", wall))?;
+                    let file = file.as_str().ok_or_else(|| format!("Textura de '{}' no es texto", wall))?;
+                    meta.wall_textures.insert(wall, file.to_string());
+                }
+            }
+
+            let seed = match fields.get("seed") {
+                Some(seed) => seed.as_u64().ok_or("La semilla debe ser un entero positivo")?,
+                None => 0,
+            };
+
+            build_maze(map, seed, meta)
+        }
+        _ => Err("Se esperaba una lista de filas o un objeto de nivel".to_string()),
+    }
+}
+
+// Same row shape maze.py emits: a list of rows, each a list of one-char strings
+fn json_map(value: &Value) -> Result<Vec<Vec<char>>, String> {
+    let rows = value.as_array().ok_or("'map' debe ser una lista de filas")?;
+    rows.iter()
+        .map(|row| {
+            let cells = row.as_array().ok_or("Cada fila debe ser una lista")?;
+            Ok(cells
+                .iter()
+                .map(|cell| cell.as_str().and_then(|s| s.chars().next()).unwrap_or(' '))
+                .collect())
+        })
+        .collect()
+}
+
+fn single_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+fn build_maze(map: Vec<Vec<char>>, seed: u64, meta: LevelMeta) -> Result<Maze, String> {
+    if map.is_empty() {
+        return Err("El laberinto no tiene filas".to_string());
+    }
+    Ok(Maze {
+        width: map.iter().map(|row| row.len()).max().unwrap_or(0),
+        height: map.len(),
+        map,
+        seed,
+        meta,
+    })
+}
+
+pub fn to_text(maze: &Maze) -> String {
+    let mut out = String::new();
+    if let Some(name) = &maze.meta.name {
+        out.push_str(&format!("name: {}\n", name));
+    }
+    out.push_str(&format!("seed: {}\n", maze.seed));
+    if let Some(angle) = maze.meta.spawn_angle {
+        out.push_str(&format!("spawn_angle: {}\n", angle.to_degrees()));
+    }
+    for (wall, file) in &maze.meta.wall_textures {
+        out.push_str(&format!("texture {}: {}\n", wall, file));
+    }
+    if let Some(floor) = &maze.meta.floor {
+        out.push_str(&format!("floor: {}\n", floor));
+    }
+    if let Some(ceiling) = &maze.meta.ceiling {
+        out.push_str(&format!("ceiling: {}\n", ceiling));
+    }
+    if let Some(count) = maze.meta.pokemon {
+        out.push_str(&format!("pokemon: {}\n", count));
+    }
+    if maze.meta.capture_all {
+        out.push_str("capture_all: true\n");
+    }
+    if let Some(count) = maze.meta.enemies {
+        out.push_str(&format!("enemies: {}\n", count));
+    }
+    out.push_str(HEADER_END);
+    out.push('\n');
+    for row in &maze.map {
+        out.extend(row.iter());
+        out.push('\n');
+    }
+    out
+}
+
+pub fn to_json(maze: &Maze) -> String {
+    let mut fields = serde_json::Map::new();
+    if let Some(name) = &maze.meta.name {
+        fields.insert("name".to_string(), Value::from(name.as_str()));
+    }
+    fields.insert("seed".to_string(), Value::from(maze.seed));
+    if let Some(angle) = maze.meta.spawn_angle {
+        fields.insert("spawn_angle".to_string(), Value::from(angle.to_degrees()));
+    }
+    if !maze.meta.wall_textures.is_empty() {
+        let textures = maze
+            .meta
+            .wall_textures
+            .iter()
+            .map(|(wall, file)| (wall.to_string(), Value::from(file.as_str())))
+            .collect();
+        fields.insert("textures".to_string(), Value::Object(textures));
+    }
+    if let Some(floor) = &maze.meta.floor {
+        fields.insert("floor".to_string(), Value::from(floor.as_str()));
+    }
+    if let Some(ceiling) = &maze.meta.ceiling {
+        fields.insert("ceiling".to_string(), Value::from(ceiling.as_str()));
+    }
+    if let Some(count) = maze.meta.pokemon {
+        fields.insert("pokemon".to_string(), Value::from(count));
+    }
+    if maze.meta.capture_all {
+        fields.insert("capture_all".to_string(), Value::from(true));
+    }
+    if let Some(count) = maze.meta.enemies {
+        fields.insert("enemies".to_string(), Value::from(count));
+    }
+
+    // One row per line keeps the grid readable in a text editor
+    let mut out = String::from("{\n");
+    for (key, value) in &fields {
+        out.push_str(&format!("  {}: {},\n", Value::from(key.as_str()), value));
+    }
+    let rows: Vec<String> = maze
+        .map
+        .iter()
+        .map(|row| {
+            let cells: Vec<Value> = row.iter().map(|c| Value::from(c.to_string())).collect();
+            format!("    {}", Value::Array(cells))
+        })
+        .collect();
+    out.push_str(&format!("  \"map\": [\n{}\n  ]\n}}\n", rows.join(",\n")));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    const SAMPLE: &str = "# nivel de prueba\nname: Ruta 1\nseed: 77\nspawn_angle: 90\ntexture |: wall1.jpg\nfloor: wall4.jpg\nceiling: sky\npokemon: 3\ncapture_all: true\nenemies: 2\n---\n+--+--+\n|p    |\n+--+  +\n|    g|\n+--+--+\n\n";
+
+    fn rows(maze: &Maze) -> Vec<String> {
+        maze.map.iter().map(|row| row.iter().collect()).collect()
+    }
+
+    #[test]
+    fn parses_text_header_and_grid() {
+        let maze = parse_text(SAMPLE).unwrap();
+        assert_eq!(maze.meta.name.as_deref(), Some("Ruta 1"));
+        assert_eq!(maze.seed, 77);
+        assert!((maze.meta.spawn_angle.unwrap() - PI / 2.0).abs() < 1e-6);
+        assert_eq!(maze.meta.wall_textures.get(&'|').map(String::as_str), Some("wall1.jpg"));
+        assert_eq!(maze.meta.floor.as_deref(), Some("wall4.jpg"));
+        assert_eq!(maze.meta.ceiling.as_deref(), Some(OPEN_SKY));
+        assert_eq!((maze.meta.pokemon, maze.meta.capture_all), (Some(3), true));
+        assert_eq!(maze.meta.enemies, Some(2));
+        assert_eq!(rows(&maze), ["+--+--+", "|p    |", "+--+  +", "|    g|", "+--+--+"]);
+        assert_eq!((maze.width, maze.height), (7, 5));
+    }
+
+    #[test]
+    fn only_texture_keys_set_wall_textures() {
+        let maze = parse_text("textures: x.jpg\ntexturez: y.jpg\n---\n+--+\n|pg|\n+--+\n").unwrap();
+        assert!(maze.meta.wall_textures.is_empty());
+        let maze = parse_text("texture  M: x.jpg\n---\n+--+\n|pg|\n+--+\n").unwrap();
+        assert_eq!(maze.meta.wall_textures.get(&'M').map(String::as_str), Some("x.jpg"));
+    }
+
+    #[test]
+    fn text_without_header_is_all_grid() {
+        let maze = parse_text("+--+\n|pg|\n+--+\n").unwrap();
+        assert_eq!(maze.seed, 0);
+        assert_eq!(maze.meta, LevelMeta::default());
+        assert_eq!(rows(&maze), ["+--+", "|pg|", "+--+"]);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let maze = parse_text(SAMPLE).unwrap();
+        let again = parse_text(&to_text(&maze)).unwrap();
+        assert_eq!(again.map, maze.map);
+        assert_eq!(again.seed, maze.seed);
+        assert_eq!(again.meta.name, maze.meta.name);
+        assert_eq!(again.meta.wall_textures, maze.meta.wall_textures);
+        assert_eq!((&again.meta.floor, &again.meta.ceiling), (&maze.meta.floor, &maze.meta.ceiling));
+        assert_eq!((again.meta.pokemon, again.meta.capture_all, again.meta.enemies), (Some(3), true, Some(2)));
+        assert!((again.meta.spawn_angle.unwrap() - maze.meta.spawn_angle.unwrap()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let maze = parse_text(SAMPLE).unwrap();
+        let again = parse_json(&to_json(&maze)).unwrap();
+        assert_eq!(again.map, maze.map);
+        assert_eq!(again.seed, 77);
+        assert_eq!(again.meta.floor, maze.meta.floor);
+        assert_eq!(again.meta.name, maze.meta.name);
+        assert_eq!(again.meta.wall_textures, maze.meta.wall_textures);
+        assert_eq!((again.meta.pokemon, again.meta.capture_all, again.meta.enemies), (Some(3), true, Some(2)));
+    }
+
+    #[test]
+    fn accepts_bare_python_json() {
+        let maze = parse_json(r#"[["+","-","-","+"],["|","p","g","|"],["+","-","-","+"]]"#).unwrap();
+        assert_eq!(rows(&maze), ["+--+", "|pg|", "+--+"]);
+    }
+
+    #[test]
+    fn rejects_bad_header_values() {
+        assert!(parse_text("seed: abc\n---\n+\n").is_err());
+        assert!(parse_text("texture ab: x.jpg\n---\n+\n").is_err());
+        let error = parse_text("texture: x.jpg\n---\n+--+\n|pg|\n+--+\n").unwrap_err();
+        assert!(error.contains("texture"), "{}", error);
+        assert!(parse_text("capture_all: quizás\n---\n+\n").is_err());
+        assert!(parse_text("name: vacío\n---\n").is_err());
+    }
+
+    #[test]
+    fn bundled_levels_load() {
+        let paths = list_levels(&levels_dir());
+        assert!(!paths.is_empty());
+        for path in paths {
+            let maze = load(&path).unwrap_or_else(|e| panic!("{:?}: {}", path, e));
+            assert!(maze.meta.name.is_some());
+        }
+    }
+
+    #[test]
+    fn level_overrides_region_surfaces() {
+        let region = SurfaceStyle { floor: "wall3.jpg".to_string(), ceiling: Some("wall5.jpg".to_string()) };
+        let open = parse_text(SAMPLE).unwrap().meta.surface_style(region.clone());
+        assert_eq!(open, SurfaceStyle { floor: "wall4.jpg".to_string(), ceiling: None });
+        assert_eq!(LevelMeta::default().surface_style(region.clone()), region);
+    }
+}
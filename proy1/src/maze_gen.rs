@@ -0,0 +1,513 @@
+use crate::maze::MazeGenerator;
+use crate::rng::Rng;
+
+/// Cuadrícula de celdas con el mismo formato de texto que `maze.py`.
+///
+/// Cada celda ocupa 3 columnas ("+--" arriba, "|  " en su fila) y 2 filas,
+/// así que un laberinto de `w`x`h` celdas produce `(2h+1)` filas de `(3w+1)` chars.
+pub struct CellGrid {
+    pub width: usize,
+    pub height: usize,
+    map: Vec<Vec<char>>,
+}
+
+impl CellGrid {
+    // Every wall standing, like the initial `hor`/`ver` arrays in maze.py
+    pub fn new(width: usize, height: usize) -> Self {
+        let width = width.max(1);
+        let height = height.max(1);
+        let mut map = Vec::with_capacity(2 * height + 1);
+        for row in 0..2 * height + 1 {
+            let line: String = if row % 2 == 0 {
+                "+--".repeat(width) + "+"
+            } else {
+                "|  ".repeat(width) + "|"
+            };
+            map.push(line.chars().collect());
+        }
+        CellGrid { width, height, map }
+    }
+
+    // Same grid with every interior wall removed (starting point for recursive division)
+    pub fn open(width: usize, height: usize) -> Self {
+        let mut grid = CellGrid::new(width, height);
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                if x + 1 < grid.width {
+                    grid.open_east(x, y);
+                }
+                if y + 1 < grid.height {
+                    grid.open_south(x, y);
+                }
+            }
+        }
+        grid
+    }
+
+    // Remove the wall between (x, y) and (x + 1, y)
+    pub fn open_east(&mut self, x: usize, y: usize) {
+        self.map[2 * y + 1][3 * (x + 1)] = ' ';
+    }
+
+    // Remove the wall between (x, y) and (x, y + 1)
+    pub fn open_south(&mut self, x: usize, y: usize) {
+        let row = 2 * (y + 1);
+        self.map[row][3 * x + 1] = ' ';
+        self.map[row][3 * x + 2] = ' ';
+    }
+
+    pub fn close_east(&mut self, x: usize, y: usize) {
+        self.map[2 * y + 1][3 * (x + 1)] = '|';
+    }
+
+    pub fn close_south(&mut self, x: usize, y: usize) {
+        let row = 2 * (y + 1);
+        self.map[row][3 * x + 1] = '-';
+        self.map[row][3 * x + 2] = '-';
+    }
+
+    // Open the wall between two orthogonally adjacent cells
+    pub fn open_between(&mut self, a: (usize, usize), b: (usize, usize)) {
+        if a.1 == b.1 {
+            self.open_east(a.0.min(b.0), a.1);
+        } else {
+            self.open_south(a.0, a.1.min(b.1));
+        }
+    }
+
+    // Put the start in the top-left cell and the goal in the bottom-right one
+    pub fn into_map(mut self) -> Vec<Vec<char>> {
+        let rows = self.map.len();
+        let cols = self.map[0].len();
+        self.map[1][1] = 'p';
+        self.map[rows - 2][cols - 2] = 'g';
+        self.map
+    }
+}
+
+// Orthogonal neighbours in the same order maze.py lists them
+pub fn neighbors(x: usize, y: usize, width: usize, height: usize) -> Vec<(usize, usize)> {
+    let mut result = Vec::with_capacity(4);
+    if x > 0 {
+        result.push((x - 1, y));
+    }
+    if y + 1 < height {
+        result.push((x, y + 1));
+    }
+    if x + 1 < width {
+        result.push((x + 1, y));
+    }
+    if y > 0 {
+        result.push((x, y - 1));
+    }
+    result
+}
+
+// Build a fully walled grid, let the generator carve it and place 'p' and 'g'
+pub fn generate_map(generator: &dyn MazeGenerator, width: usize, height: usize, rng: &mut Rng) -> Vec<Vec<char>> {
+    let mut grid = CellGrid::new(width, height);
+    generator.carve(&mut grid, rng);
+    grid.into_map()
+}
+
+/// Backtracking recursivo (igual que `walk` en maze.py) con una pila explícita,
+/// para no desbordar la pila en laberintos grandes. Pasillos largos y sinuosos.
+pub struct RecursiveBacktracker;
+
+impl MazeGenerator for RecursiveBacktracker {
+    fn name(&self) -> &'static str {
+        "Backtracking recursivo"
+    }
+
+    fn carve(&self, grid: &mut CellGrid, rng: &mut Rng) {
+        let (width, height) = (grid.width, grid.height);
+        let mut visited = vec![vec![false; width]; height];
+
+        let start = (rng.gen_range(width), rng.gen_range(height));
+        visited[start.1][start.0] = true;
+
+        // Each frame keeps its own shuffled neighbour list, like the Python recursion
+        let mut first = neighbors(start.0, start.1, width, height);
+        rng.shuffle(&mut first);
+        let mut stack = vec![(start, first)];
+
+        while let Some((cell, pending)) = stack.last_mut() {
+            let cell = *cell;
+            let Some(next) = pending.pop() else {
+                stack.pop();
+                continue;
+            };
+            if visited[next.1][next.0] {
+                continue;
+            }
+
+            grid.open_between(cell, next);
+            visited[next.1][next.0] = true;
+
+            let mut dirs = neighbors(next.0, next.1, width, height);
+            rng.shuffle(&mut dirs);
+            stack.push((next, dirs));
+        }
+    }
+}
+
+/// Prim aleatorio: crece desde una celda eligiendo fronteras al azar.
+/// Muchos callejones sin salida cortos.
+pub struct Prim;
+
+impl MazeGenerator for Prim {
+    fn name(&self) -> &'static str {
+        "Prim"
+    }
+
+    fn carve(&self, grid: &mut CellGrid, rng: &mut Rng) {
+        let (width, height) = (grid.width, grid.height);
+        let mut in_maze = vec![vec![false; width]; height];
+        let mut in_frontier = vec![vec![false; width]; height];
+        let mut frontier = Vec::new();
+
+        let start = (rng.gen_range(width), rng.gen_range(height));
+        in_maze[start.1][start.0] = true;
+        for (nx, ny) in neighbors(start.0, start.1, width, height) {
+            in_frontier[ny][nx] = true;
+            frontier.push((nx, ny));
+        }
+
+        while !frontier.is_empty() {
+            let (x, y) = frontier.swap_remove(rng.gen_range(frontier.len()));
+
+            let carved: Vec<_> = neighbors(x, y, width, height)
+                .into_iter()
+                .filter(|&(nx, ny)| in_maze[ny][nx])
+                .collect();
+            let target = carved[rng.gen_range(carved.len())];
+            grid.open_between((x, y), target);
+            in_maze[y][x] = true;
+
+            for (nx, ny) in neighbors(x, y, width, height) {
+                if !in_maze[ny][nx] && !in_frontier[ny][nx] {
+                    in_frontier[ny][nx] = true;
+                    frontier.push((nx, ny));
+                }
+            }
+        }
+    }
+}
+
+/// Kruskal aleatorio: une conjuntos de celdas quitando paredes en orden aleatorio.
+/// Ramificación uniforme con muchos callejones cortos.
+pub struct Kruskal;
+
+impl MazeGenerator for Kruskal {
+    fn name(&self) -> &'static str {
+        "Kruskal"
+    }
+
+    fn carve(&self, grid: &mut CellGrid, rng: &mut Rng) {
+        let (width, height) = (grid.width, grid.height);
+
+        let mut edges = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                if x + 1 < width {
+                    edges.push(((x, y), (x + 1, y)));
+                }
+                if y + 1 < height {
+                    edges.push(((x, y), (x, y + 1)));
+                }
+            }
+        }
+        rng.shuffle(&mut edges);
+
+        // Union-find over cell indices
+        let mut parent: Vec<usize> = (0..width * height).collect();
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+
+        for (a, b) in edges {
+            let root_a = find(&mut parent, a.1 * width + a.0);
+            let root_b = find(&mut parent, b.1 * width + b.0);
+            if root_a != root_b {
+                parent[root_b] = root_a;
+                grid.open_between(a, b);
+            }
+        }
+    }
+}
+
+/// Eller: genera fila por fila manteniendo conjuntos conectados.
+/// Pasillos horizontales largos con bajadas frecuentes.
+pub struct Eller;
+
+impl MazeGenerator for Eller {
+    fn name(&self) -> &'static str {
+        "Eller"
+    }
+
+    fn carve(&self, grid: &mut CellGrid, rng: &mut Rng) {
+        let (width, height) = (grid.width, grid.height);
+        let mut sets: Vec<usize> = (0..width).collect();
+        let mut next_set = width;
+
+        for y in 0..height {
+            let last_row = y + 1 == height;
+
+            // Join neighbours in different sets (always on the last row so everything connects)
+            for x in 0..width.saturating_sub(1) {
+                if sets[x] != sets[x + 1] && (last_row || rng.gen_bool()) {
+                    grid.open_east(x, y);
+                    let (keep, old) = (sets[x], sets[x + 1]);
+                    for set in sets.iter_mut() {
+                        if *set == old {
+                            *set = keep;
+                        }
+                    }
+                }
+            }
+
+            if last_row {
+                break;
+            }
+
+            // Every set sends at least one cell down; group in row order to stay deterministic
+            let mut groups: Vec<(usize, Vec<usize>)> = Vec::new();
+            for (x, &set) in sets.iter().enumerate() {
+                match groups.iter_mut().find(|(id, _)| *id == set) {
+                    Some((_, cells)) => cells.push(x),
+                    None => groups.push((set, vec![x])),
+                }
+            }
+
+            let mut next_row = vec![None; width];
+            for (set, mut cells) in groups {
+                rng.shuffle(&mut cells);
+                let drops = 1 + rng.gen_range(cells.len());
+                for &x in &cells[..drops] {
+                    grid.open_south(x, y);
+                    next_row[x] = Some(set);
+                }
+            }
+
+            sets = next_row
+                .into_iter()
+                .map(|set| set.unwrap_or_else(|| {
+                    next_set += 1;
+                    next_set
+                }))
+                .collect();
+        }
+    }
+}
+
+/// Wilson: caminatas aleatorias con borrado de ciclos.
+/// Muestra laberintos perfectos de forma uniforme, sin sesgo visible.
+pub struct Wilson;
+
+impl MazeGenerator for Wilson {
+    fn name(&self) -> &'static str {
+        "Wilson"
+    }
+
+    fn carve(&self, grid: &mut CellGrid, rng: &mut Rng) {
+        let (width, height) = (grid.width, grid.height);
+        let mut in_maze = vec![vec![false; width]; height];
+        let mut next: Vec<Vec<(usize, usize)>> = vec![vec![(0, 0); width]; height];
+
+        in_maze[rng.gen_range(height)][rng.gen_range(width)] = true;
+
+        for start_y in 0..height {
+            for start_x in 0..width {
+                // Random walk until the maze is hit; overwriting `next` erases loops
+                let mut cell = (start_x, start_y);
+                while !in_maze[cell.1][cell.0] {
+                    let options = neighbors(cell.0, cell.1, width, height);
+                    let step = options[rng.gen_range(options.len())];
+                    next[cell.1][cell.0] = step;
+                    cell = step;
+                }
+
+                // Carve the loop-erased path into the maze
+                let mut cell = (start_x, start_y);
+                while !in_maze[cell.1][cell.0] {
+                    let step = next[cell.1][cell.0];
+                    grid.open_between(cell, step);
+                    in_maze[cell.1][cell.0] = true;
+                    cell = step;
+                }
+            }
+        }
+    }
+}
+
+/// Árbol binario: cada celda abre hacia el norte o el oeste.
+/// Pasillos rectos a lo largo del borde superior e izquierdo; el más fácil.
+pub struct BinaryTree;
+
+impl MazeGenerator for BinaryTree {
+    fn name(&self) -> &'static str {
+        "Árbol binario"
+    }
+
+    fn carve(&self, grid: &mut CellGrid, rng: &mut Rng) {
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                match (x > 0, y > 0) {
+                    (true, true) if rng.gen_bool() => grid.open_between((x, y), (x - 1, y)),
+                    (true, true) => grid.open_between((x, y), (x, y - 1)),
+                    (true, false) => grid.open_between((x, y), (x - 1, y)),
+                    (false, true) => grid.open_between((x, y), (x, y - 1)),
+                    (false, false) => {}
+                }
+            }
+        }
+    }
+}
+
+/// División recursiva: parte cámaras abiertas con paredes de un solo hueco.
+/// Paredes largas y rectas que forman habitaciones.
+pub struct RecursiveDivision;
+
+impl MazeGenerator for RecursiveDivision {
+    fn name(&self) -> &'static str {
+        "División recursiva"
+    }
+
+    fn carve(&self, grid: &mut CellGrid, rng: &mut Rng) {
+        *grid = CellGrid::open(grid.width, grid.height);
+
+        // Chambers as (x, y, width, height) in cells
+        let mut chambers = vec![(0, 0, grid.width, grid.height)];
+        while let Some((x, y, w, h)) = chambers.pop() {
+            if w < 2 || h < 2 {
+                continue;
+            }
+
+            let horizontal = if w == h { rng.gen_bool() } else { h > w };
+            if horizontal {
+                // Wall below row `wall_y`, with a single gap at `gap_x`
+                let wall_y = y + rng.gen_range(h - 1);
+                let gap_x = x + rng.gen_range(w);
+                for cx in x..x + w {
+                    if cx != gap_x {
+                        grid.close_south(cx, wall_y);
+                    }
+                }
+                chambers.push((x, y, w, wall_y - y + 1));
+                chambers.push((x, wall_y + 1, w, y + h - wall_y - 1));
+            } else {
+                let wall_x = x + rng.gen_range(w - 1);
+                let gap_y = y + rng.gen_range(h);
+                for cy in y..y + h {
+                    if cy != gap_y {
+                        grid.close_east(wall_x, cy);
+                    }
+                }
+                chambers.push((x, y, wall_x - x + 1, h));
+                chambers.push((wall_x + 1, y, x + w - wall_x - 1, h));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{is_wall_cell, MazeAlgorithm};
+    use std::collections::VecDeque;
+
+    // Count open cells reachable from 'p' through non-wall chars
+    fn reachable_cells(map: &[Vec<char>]) -> usize {
+        let mut seen = vec![vec![false; map[0].len()]; map.len()];
+        let mut queue = VecDeque::from([(1usize, 1usize)]);
+        seen[1][1] = true;
+        let mut count = 0;
+        while let Some((x, y)) = queue.pop_front() {
+            count += 1;
+            for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                if !seen[ny][nx] && !is_wall_cell(map[ny][nx]) {
+                    seen[ny][nx] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn same_seed_gives_same_maze() {
+        for algorithm in MazeAlgorithm::ALL {
+            let generator = algorithm.generator();
+            let a = generate_map(generator.as_ref(), 8, 6, &mut Rng::new(42));
+            let b = generate_map(generator.as_ref(), 8, 6, &mut Rng::new(42));
+            let c = generate_map(generator.as_ref(), 8, 6, &mut Rng::new(43));
+            assert_eq!(a, b, "{}", generator.name());
+            assert_ne!(a, c, "{}", generator.name());
+        }
+    }
+
+    #[test]
+    fn seed_pins_exact_map() {
+        // Pinned output: any change to Rng or the generator breaks shared seeds
+        let maze = crate::maze::Maze::generate(4, 3, &RecursiveBacktracker, 2024);
+        let expected = [
+            "+--+--+--+--+",
+            "|p |     |  |",
+            "+  +  +--+  +",
+            "|     |     |",
+            "+  +--+--+  +",
+            "|          g|",
+            "+--+--+--+--+",
+        ];
+        let rows: Vec<String> = maze.map.iter().map(|row| row.iter().collect()).collect();
+        assert_eq!(rows, expected);
+        assert_eq!(maze.seed, 2024);
+    }
+
+    #[test]
+    fn matches_python_layout() {
+        let map = generate_map(&RecursiveBacktracker, 4, 3, &mut Rng::new(7));
+        assert_eq!(map.len(), 7);
+        assert!(map.iter().all(|row| row.len() == 13));
+        assert_eq!(map[1][1], 'p');
+        assert_eq!(map[5][11], 'g');
+        // Every corner post stays in place
+        for row in (0..7).step_by(2) {
+            for col in (0..13).step_by(3) {
+                assert_eq!(map[row][col], '+');
+            }
+        }
+    }
+
+    fn assert_perfect(map: &[Vec<char>], w: usize, h: usize, name: &str) {
+        assert_eq!(crate::validate::validate(map), [], "{} failed validation", name);
+
+        // All open chars form one connected region
+        let total_open = map.iter().flatten().filter(|&&c| !is_wall_cell(c)).count();
+        assert_eq!(reachable_cells(map), total_open, "{} left cells unreachable", name);
+
+        // A spanning tree over w*h cells carves exactly w*h - 1 passages
+        let east = (0..h).flat_map(|y| (1..w).map(move |x| (x, y)))
+            .filter(|&(x, y)| map[2 * y + 1][3 * x] == ' ')
+            .count();
+        let south = (1..h).flat_map(|y| (0..w).map(move |x| (x, y)))
+            .filter(|&(x, y)| map[2 * y][3 * x + 1] == ' ')
+            .count();
+        assert_eq!(east + south, w * h - 1, "{} carved loops", name);
+    }
+
+    #[test]
+    fn every_algorithm_produces_a_perfect_maze() {
+        for algorithm in MazeAlgorithm::ALL {
+            let generator = algorithm.generator();
+            for (w, h, seed) in [(12, 12, 1234), (4, 4, 1), (9, 3, 99), (1, 5, 5)] {
+                let map = generate_map(generator.as_ref(), w, h, &mut Rng::new(seed));
+                assert_perfect(&map, w, h, generator.name());
+            }
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use std::cmp::Reverse;
+use std::time::Duration;
+
+use raylib::prelude::Vector2;
+
+use crate::caster::trace_ray;
+use crate::maze::{is_wall_cell, Maze, WORLD_BLOCK_SIZE};
+use crate::rng::Rng;
+use crate::solver::{cell_center, find_path, world_to_cell};
+use crate::sprites::{AnimatedSprite, GASTLY};
+
+// Speeds in world units per second
+const PATROL_SPEED: f32 = 45.0;
+const CHASE_SPEED: f32 = 95.0;
+// An NPC spots the player up to this far away, if no wall is in between
+const SIGHT_RANGE: f32 = 140.0;
+// Closer than this the player is caught
+const CONTACT_RADIUS: f32 = 9.0;
+// Enemies spawn at least this many cells (Manhattan) away from 'p'
+const MIN_SPAWN_DISTANCE: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NpcState {
+    Patrol, // Wander between random open cells
+    Chase,  // Head for the player, or where they were last seen
+    Return, // Lost the player: walk back home, then patrol again
+}
+
+/// Enemigo que deambula por el laberinto y persigue al jugador si lo ve.
+pub struct Npc {
+    pub position: Vector2,
+    pub state: NpcState,
+    pub sprite: AnimatedSprite,
+    home: (usize, usize),
+    path: Vec<(usize, usize)>,      // Cells still to walk, next one last
+    target: Option<(usize, usize)>, // Cell the current path leads to
+    last_seen: Option<(usize, usize)>,
+    rng: Rng,
+}
+
+impl Npc {
+    pub fn new(home: (usize, usize), seed: u64) -> Self {
+        let position = cell_center(home, WORLD_BLOCK_SIZE as f32);
+        Npc {
+            position,
+            state: NpcState::Patrol,
+            sprite: AnimatedSprite::new(position, GASTLY, Duration::from_millis(250)),
+            home,
+            path: Vec::new(),
+            target: None,
+            last_seen: None,
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Avanza la máquina de estados y mueve al NPC `dt` segundos.
+    pub fn update(&mut self, map: &[Vec<char>], player_pos: Vector2, dt: f32) {
+        let block = WORLD_BLOCK_SIZE as f32;
+        let sees_player = can_see(map, self.position, player_pos);
+        if sees_player {
+            self.state = NpcState::Chase;
+            self.last_seen = world_to_cell(player_pos, block);
+        }
+
+        match self.state {
+            NpcState::Patrol => {
+                if self.path.is_empty() {
+                    let destination = self.random_open_cell(map);
+                    self.head_for(map, destination);
+                }
+                self.walk(PATROL_SPEED * dt);
+            }
+            NpcState::Chase => {
+                let here = world_to_cell(self.position, block);
+                if sees_player && here == self.last_seen {
+                    // Same cell: the last stretch is a straight line
+                    self.step_towards(player_pos, CHASE_SPEED * dt);
+                } else {
+                    self.head_for(map, self.last_seen);
+                    self.walk(CHASE_SPEED * dt);
+                    if self.path.is_empty() && !sees_player {
+                        self.send_home();
+                    }
+                }
+            }
+            NpcState::Return => {
+                self.head_for(map, Some(self.home));
+                self.walk(PATROL_SPEED * dt);
+                if self.path.is_empty() {
+                    self.state = NpcState::Patrol;
+                    self.target = None;
+                }
+            }
+        }
+
+        self.sprite.position = self.position;
+        self.sprite.update();
+    }
+
+    // Give up the chase, e.g. after catching the player
+    pub fn send_home(&mut self) {
+        self.state = NpcState::Return;
+        self.last_seen = None;
+    }
+
+    pub fn touches(&self, pos: Vector2) -> bool {
+        self.position.distance_to(pos) <= CONTACT_RADIUS
+    }
+
+    // Re-plan only when the destination changes; the path is stored back to front
+    fn head_for(&mut self, map: &[Vec<char>], destination: Option<(usize, usize)>) {
+        if destination == self.target {
+            return;
+        }
+        self.target = destination;
+        self.path.clear();
+        let start = world_to_cell(self.position, WORLD_BLOCK_SIZE as f32);
+        if let (Some(start), Some(goal)) = (start, destination)
+            && let Some(path) = find_path(map, start, goal)
+        {
+            self.path = path.into_iter().rev().collect();
+        }
+    }
+
+    // Follow the path through cell centers, spending at most `distance` world units
+    fn walk(&mut self, mut distance: f32) {
+        let block = WORLD_BLOCK_SIZE as f32;
+        while distance > 0.0 {
+            let Some(&next) = self.path.last() else { break };
+            let center = cell_center(next, block);
+            let remaining = self.position.distance_to(center);
+            if remaining <= distance {
+                self.position = center;
+                distance -= remaining;
+                self.path.pop();
+            } else {
+                self.step_towards(center, distance);
+                break;
+            }
+        }
+    }
+
+    fn step_towards(&mut self, point: Vector2, distance: f32) {
+        let offset = point - self.position;
+        let length = offset.length();
+        if length <= distance {
+            self.position = point;
+        } else if length > 0.0 {
+            self.position += offset * (distance / length);
+        }
+    }
+
+    fn random_open_cell(&mut self, map: &[Vec<char>]) -> Option<(usize, usize)> {
+        let cells = open_cells(map);
+        (!cells.is_empty()).then(|| cells[self.rng.gen_range(cells.len())])
+    }
+}
+
+/// Hay línea de visión si el rayo hacia el jugador no choca antes con una pared.
+pub fn can_see(map: &[Vec<char>], from: Vector2, to: Vector2) -> bool {
+    let offset = to - from;
+    let distance = offset.length();
+    if distance > SIGHT_RANGE {
+        return false;
+    }
+    if distance < f32::EPSILON {
+        return true;
+    }
+    trace_ray(map, from, offset.y.atan2(offset.x), WORLD_BLOCK_SIZE).distance >= distance
+}
+
+/// Coloca `count` enemigos lejos de 'p', de forma reproducible con la semilla del laberinto.
+pub fn spawn_npcs(maze: &Maze, count: usize) -> Vec<Npc> {
+    let mut rng = Rng::new(maze.seed ^ 0x6A57_17EE_0BAD_C0DE);
+    let spawn = maze
+        .map
+        .iter()
+        .enumerate()
+        .find_map(|(y, row)| row.iter().position(|&c| c == 'p').map(|x| (x, y)))
+        .unwrap_or((1, 1));
+    let distance = |&(x, y): &(usize, usize)| x.abs_diff(spawn.0) + y.abs_diff(spawn.1);
+
+    let mut cells: Vec<(usize, usize)> = open_cells(&maze.map)
+        .into_iter()
+        .filter(|&(x, y)| maze.map[y][x] == ' ')
+        .collect();
+    rng.shuffle(&mut cells);
+    // Small mazes may have nothing that far; then the farthest cells come first
+    if !cells.iter().any(|cell| distance(cell) >= MIN_SPAWN_DISTANCE) {
+        cells.sort_by_key(|cell| Reverse(distance(cell)));
+    } else {
+        cells.retain(|cell| distance(cell) >= MIN_SPAWN_DISTANCE);
+    }
+
+    cells
+        .into_iter()
+        .take(count)
+        .map(|home| Npc::new(home, rng.next_u64()))
+        .collect()
+}
+
+fn open_cells(map: &[Vec<char>]) -> Vec<(usize, usize)> {
+    map.iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|&(_, &c)| !is_wall_cell(c))
+                .map(move |(x, _)| (x, y))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A corridor along the top with a side room behind a wall
+    const ROWS: [&str; 5] = ["+----------+", "|p        g|", "|-------- -|", "|         ||", "+----------+"];
+
+    #[test]
+    fn walls_block_line_of_sight() {
+        let map = Maze::from_rows(&ROWS).map;
+        let corridor = cell_center((2, 1), 20.0);
+        assert!(can_see(&map, corridor, cell_center((7, 1), 20.0)));
+        assert!(!can_see(&map, corridor, cell_center((2, 3), 20.0)));
+        // Too far even with nothing in between
+        assert!(!can_see(&map, corridor, Vector2::new(corridor.x + SIGHT_RANGE + 1.0, corridor.y)));
+    }
+
+    #[test]
+    fn chases_then_returns_home_then_patrols() {
+        let map = Maze::from_rows(&ROWS).map;
+        let mut npc = Npc::new((8, 1), 1);
+
+        // Player in plain sight down the corridor
+        npc.update(&map, cell_center((5, 1), 20.0), 0.1);
+        assert_eq!(npc.state, NpcState::Chase);
+        assert!(npc.position.x < cell_center((8, 1), 20.0).x);
+
+        // Player slips into the side room: the NPC goes to where it last saw them, then gives up
+        let hidden = cell_center((2, 3), 20.0);
+        for _ in 0..20 {
+            npc.update(&map, hidden, 0.1);
+            if npc.state != NpcState::Chase {
+                break;
+            }
+        }
+        assert_eq!(npc.state, NpcState::Return);
+        assert_eq!(world_to_cell(npc.position, 20.0), Some((5, 1)));
+
+        for _ in 0..40 {
+            npc.update(&map, hidden, 0.1);
+            if npc.state == NpcState::Patrol {
+                break;
+            }
+        }
+        assert_eq!(npc.state, NpcState::Patrol);
+        assert_eq!(world_to_cell(npc.position, 20.0), Some((8, 1)));
+    }
+
+    #[test]
+    fn walking_stays_on_open_cells() {
+        let map = Maze::from_rows(&ROWS).map;
+        let mut npc = Npc::new((9, 3), 7);
+        for _ in 0..300 {
+            npc.update(&map, Vector2::new(-100.0, -100.0), 1.0 / 30.0);
+            let (x, y) = world_to_cell(npc.position, 20.0).unwrap();
+            assert!(!is_wall_cell(map[y][x]), "dentro de una pared en {:?}", (x, y));
+        }
+    }
+
+    #[test]
+    fn catches_player_on_contact() {
+        let npc = Npc::new((3, 1), 0);
+        assert!(npc.touches(cell_center((3, 1), 20.0) + Vector2::new(5.0, 0.0)));
+        assert!(!npc.touches(cell_center((4, 1), 20.0)));
+    }
+
+    #[test]
+    fn spawns_away_from_start() {
+        let maze = Maze { seed: 5, ..Maze::from_rows(&ROWS) };
+        let npcs = spawn_npcs(&maze, 2);
+        assert_eq!(npcs.len(), 2);
+        for npc in &npcs {
+            let (x, y) = world_to_cell(npc.position, 20.0).unwrap();
+            assert!(x.abs_diff(1) + y.abs_diff(1) >= MIN_SPAWN_DISTANCE);
+            assert_eq!(maze.map[y][x], ' ');
+        }
+    }
+}
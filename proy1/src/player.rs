@@ -1,11 +1,34 @@
 use raylib::prelude::*;
 use std::f32::consts::PI;
-use gilrs::{Gilrs, Button, Axis, Event, EventType};
+use crate::input::{Action, Input};
+use crate::maze::{is_wall_cell, WORLD_BLOCK_SIZE};
+use crate::settings::Settings;
+
+// World units the player keeps between their center and any wall; under half a corridor (block) wide
+pub const PLAYER_RADIUS: f32 = 5.0;
+// Halvings tried when a move along one axis is blocked, to end up next to the wall
+const CONTACT_STEPS: usize = 6;
+// Share of the stamina meter refilled per second while not sprinting
+const STAMINA_RECOVERY: f32 = 0.25;
+// After running out, the meter has to be back to this before sprinting again
+const STAMINA_TO_SPRINT: f32 = 0.3;
+// Slower than this (units per second) with no input counts as stopped
+const REST_SPEED: f32 = 1.0;
 
 pub struct Player {
     pub pos: Vector2,
     pub a: f32,
     pub fov: f32, // field of view
+    pub velocity: Vector2, // World units per second
+    pub stamina: f32,      // Sprint meter, 0.0 to 1.0
+    exhausted: bool,       // Emptied the meter; no sprinting until it refills to STAMINA_TO_SPRINT
+}
+
+/// Posición y orientación del jugador al final de un paso de simulación.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose {
+    pub pos: Vector2,
+    pub a: f32,
 }
 
 impl Player {
@@ -14,48 +37,42 @@ impl Player {
             pos,
             a,
             fov,
+            velocity: Vector2::zero(),
+            stamina: 1.0,
+            exhausted: false,
         }
     }
 
+    /// Lleva al jugador a `(pos, a)` detenido y con la resistencia llena (inicio, atrapado, nuevo laberinto).
+    pub fn respawn(&mut self, (pos, a): (Vector2, f32)) {
+        *self = Player::new(pos, a, self.fov);
+    }
+
     // Camera movement methods
     pub fn move_forward(&mut self, speed: f32, maze: &Vec<Vec<char>>) {
-        let new_x = self.pos.x + speed * self.a.cos();
-        let new_y = self.pos.y + speed * self.a.sin();
-        
-        if is_position_valid(new_x, new_y, maze, 20.0) {
-            self.pos.x = new_x;
-            self.pos.y = new_y;
-        }
+        self.move_by(speed * self.a.cos(), speed * self.a.sin(), maze);
     }
 
     pub fn move_backward(&mut self, speed: f32, maze: &Vec<Vec<char>>) {
-        let new_x = self.pos.x - speed * self.a.cos();
-        let new_y = self.pos.y - speed * self.a.sin();
-        
-        if is_position_valid(new_x, new_y, maze, 20.0) {
-            self.pos.x = new_x;
-            self.pos.y = new_y;
-        }
+        self.move_by(-speed * self.a.cos(), -speed * self.a.sin(), maze);
     }
 
     pub fn strafe_left(&mut self, speed: f32, maze: &Vec<Vec<char>>) {
-        let new_x = self.pos.x + speed * (self.a - PI / 2.0).cos();
-        let new_y = self.pos.y + speed * (self.a - PI / 2.0).sin();
-        
-        if is_position_valid(new_x, new_y, maze, 20.0) {
-            self.pos.x = new_x;
-            self.pos.y = new_y;
-        }
+        self.move_by(speed * (self.a - PI / 2.0).cos(), speed * (self.a - PI / 2.0).sin(), maze);
     }
 
     pub fn strafe_right(&mut self, speed: f32, maze: &Vec<Vec<char>>) {
-        let new_x = self.pos.x + speed * (self.a + PI / 2.0).cos();
-        let new_y = self.pos.y + speed * (self.a + PI / 2.0).sin();
-        
-        if is_position_valid(new_x, new_y, maze, 20.0) {
-            self.pos.x = new_x;
-            self.pos.y = new_y;
-        }
+        self.move_by(speed * (self.a + PI / 2.0).cos(), speed * (self.a + PI / 2.0).sin(), maze);
+    }
+
+    /// Desplaza al jugador resolviendo X e Y por separado: si un eje choca el otro sigue,
+    /// así que al caminar en diagonal contra una pared se desliza a lo largo de ella.
+    pub fn move_by(&mut self, dx: f32, dy: f32, maze: &Vec<Vec<char>>) {
+        let block = WORLD_BLOCK_SIZE as f32;
+        let y = self.pos.y;
+        self.pos.x = slide_axis(self.pos.x, dx, |x| circle_fits(x, y, maze, block));
+        let x = self.pos.x;
+        self.pos.y = slide_axis(self.pos.y, dy, |y| circle_fits(x, y, maze, block));
     }
 
     pub fn rotate(&mut self, angle_delta: f32) {
@@ -68,150 +85,327 @@ impl Player {
         }
     }
 
-    // Keyboard input handling
-    pub fn update_keyboard(&mut self, rl: &RaylibHandle, maze: &Vec<Vec<char>>) {
-        const MOVE_SPEED: f32 = 3.0;
-        const ROTATION_SPEED: f32 = PI / 30.0;
-
-        // Movement
-        if rl.is_key_down(KeyboardKey::KEY_W) || rl.is_key_down(KeyboardKey::KEY_UP) {
-            self.move_forward(MOVE_SPEED, maze);
-        }
-        if rl.is_key_down(KeyboardKey::KEY_S) || rl.is_key_down(KeyboardKey::KEY_DOWN) {
-            self.move_backward(MOVE_SPEED, maze);
-        }
-        if rl.is_key_down(KeyboardKey::KEY_A) {
-            self.strafe_left(MOVE_SPEED, maze);
-        }
-        if rl.is_key_down(KeyboardKey::KEY_D) {
-            self.strafe_right(MOVE_SPEED, maze);
-        }
+    pub fn pose(&self) -> Pose {
+        Pose { pos: self.pos, a: self.a }
+    }
 
-        // Keyboard rotation
-        if rl.is_key_down(KeyboardKey::KEY_LEFT) {
-            self.rotate(ROTATION_SPEED);
-        }
-        if rl.is_key_down(KeyboardKey::KEY_RIGHT) {
-            self.rotate(-ROTATION_SPEED);
+    /// Jugador a dibujar entre el paso `previous` y el actual: `alpha` 0.0 es `previous`, 1.0 el actual.
+    pub fn interpolated(&self, previous: Pose, alpha: f32) -> Player {
+        let at = |pos, a| Player { pos, a, fov: self.fov, velocity: self.velocity, stamina: self.stamina, exhausted: self.exhausted };
+        let (dx, dy) = (self.pos.x - previous.pos.x, self.pos.y - previous.pos.y);
+        // Respawns and new mazes jump instead of gliding across the map
+        if dx.hypot(dy) > WORLD_BLOCK_SIZE as f32 {
+            return at(self.pos, self.a);
         }
+        // The short way around, so turning past 0 doesn't spin the other way
+        let turn = (self.a - previous.a + PI).rem_euclid(2.0 * PI) - PI;
+        let pos = Vector2::new(previous.pos.x + dx * alpha, previous.pos.y + dy * alpha);
+        at(pos, (previous.a + turn * alpha).rem_euclid(2.0 * PI))
     }
 
-    // Gamepad input handling
-    pub fn update_gamepad(&mut self, gilrs: &mut Gilrs, maze: &Vec<Vec<char>>) {
-        const MOVE_SPEED: f32 = 3.0;
-        const ROTATION_SPEED: f32 = PI / 30.0;
-        const STICK_DEADZONE: f32 = 0.1;
+    /// Un paso de simulación de `dt` segundos con las acciones de este frame.
+    ///
+    /// El input pide una velocidad (la inclinación del stick escala la velocidad máxima, correr la
+    /// multiplica) y la velocidad actual se acerca a ella con la aceleración de los ajustes; sin
+    /// input, la fricción la frena. Los giros siguen siendo directos.
+    pub fn update_actions(&mut self, input: &Input, maze: &Vec<Vec<char>>, settings: &Settings, dt: f32) {
+        // Movement: keys give 1.0, sticks how far they are pushed; diagonals are no faster
+        let forward = input.value(Action::MoveForward) - input.value(Action::MoveBackward);
+        let side = input.value(Action::StrafeRight) - input.value(Action::StrafeLeft);
+        let (sin, cos) = self.a.sin_cos();
+        let wish = Vector2::new(forward * cos - side * sin, forward * sin + side * cos);
+        let amount = wish.length().min(1.0);
 
-        // Handle gamepad events (sin debug verbose)
-        while let Some(Event { id, event, time: _ }) = gilrs.next_event() {
-            match event {
-                EventType::Connected => {
-                    println!("🎮 Controller conectado: {}", gilrs.gamepad(id).name());
-                }
-                EventType::Disconnected => {
-                    println!("🎮 Controller desconectado");
-                }
-                _ => {}
+        let sprinting = self.update_stamina(input.held(Action::Sprint) && amount > 0.0, settings, dt);
+        if amount > 0.0 {
+            let top_speed = settings.move_speed * if sprinting { settings.sprint_multiplier } else { 1.0 };
+            let target = wish * (amount * top_speed / wish.length());
+            let change = target - self.velocity;
+            let max_change = settings.acceleration * dt;
+            self.velocity += if change.length() > max_change { change * (max_change / change.length()) } else { change };
+        } else {
+            self.velocity *= (-settings.friction * dt).exp();
+            if self.velocity.length() < REST_SPEED {
+                self.velocity = Vector2::zero();
             }
         }
 
-        // Check active gamepads for continuous input
-        let gamepad_count = gilrs.gamepads().count();
-        if gamepad_count == 0 {
-            return; // No hay gamepads, salir temprano
+        // Walls take away the part of the velocity that pushes into them; the rest slides on
+        let (start, step) = (self.pos, self.velocity * dt);
+        self.move_by(step.x, step.y, maze);
+        if (self.pos.x - start.x).abs() < step.x.abs() {
+            self.velocity.x = (self.pos.x - start.x) / dt;
+        }
+        if (self.pos.y - start.y).abs() < step.y.abs() {
+            self.velocity.y = (self.pos.y - start.y) / dt;
         }
-        
-        for (_id, gamepad) in gilrs.gamepads() {
-            // Left stick for movement
-            let left_x = gamepad.value(Axis::LeftStickX);
-            let left_y = gamepad.value(Axis::LeftStickY);
-
-            // Right stick for camera rotation
-            let right_x = gamepad.value(Axis::RightStickX);
-
-            // D-pad for movement (digital) - REMOVED: now only for menus
-            // Face buttons are now only for menu interaction, not movement
-            
-            // Only analog sticks for player movement in-game
-            // This prevents interference with menu navigation
-
-            // Triggers for rotation (alternative)
-            let left_trigger = gamepad.value(Axis::LeftZ);
-            let right_trigger = gamepad.value(Axis::RightZ);
-
-            // Movement with left stick (with deadzone)
-            if left_y.abs() > STICK_DEADZONE {
-                if left_y > 0.0 {
-                    self.move_forward(MOVE_SPEED * left_y, maze);
-                } else {
-                    self.move_backward(MOVE_SPEED * -left_y, maze);
-                }
-            }
 
-            if left_x.abs() > STICK_DEADZONE {
-                if left_x > 0.0 {
-                    self.strafe_right(MOVE_SPEED * left_x, maze);
-                } else {
-                    self.strafe_left(MOVE_SPEED * -left_x, maze);
-                }
-            }
+        // Rotation; a growing angle turns the view to the right
+        let turn = input.value(Action::TurnRight) - input.value(Action::TurnLeft);
+        if turn != 0.0 {
+            self.rotate(settings.rotation_speed.to_radians() * dt * turn);
+        }
+    }
 
-            // Camera rotation with right stick - FIXED: removed negative sign
-            if right_x.abs() > STICK_DEADZONE {
-                self.rotate(right_x * ROTATION_SPEED * 2.0); // Multiply for faster rotation
-            }
+    // False from running out of stamina until the meter is back to STAMINA_TO_SPRINT
+    pub fn can_sprint(&self) -> bool {
+        !self.exhausted || self.stamina >= STAMINA_TO_SPRINT
+    }
 
-            // Trigger rotation (alternative control scheme)
-            if left_trigger > 0.1 {
-                self.rotate(ROTATION_SPEED * left_trigger);
-            }
-            if right_trigger > 0.1 {
-                self.rotate(-ROTATION_SPEED * right_trigger);
-            }
+    // Drains the meter while sprinting and refills it otherwise; returns whether this step sprints
+    fn update_stamina(&mut self, wants_to_sprint: bool, settings: &Settings, dt: f32) -> bool {
+        if self.exhausted && self.stamina >= STAMINA_TO_SPRINT {
+            self.exhausted = false;
+        }
+        let sprinting = wants_to_sprint && !self.exhausted;
+        if sprinting {
+            self.stamina = (self.stamina - dt / settings.stamina_seconds).max(0.0);
+            self.exhausted = self.stamina == 0.0;
+        } else {
+            self.stamina = (self.stamina + STAMINA_RECOVERY * dt).min(1.0);
         }
+        sprinting
     }
 
     // Mouse input handling
-    pub fn update_mouse(&mut self, rl: &RaylibHandle) {
-        const MOUSE_SENSITIVITY: f32 = 0.002; // Adjust for sensitivity
-        
+    pub fn update_mouse(&mut self, rl: &RaylibHandle, settings: &Settings) {
         let mouse_delta_x = rl.get_mouse_delta().x;
         
         // Only rotate if there's mouse movement - FIXED: removed negative sign
         if mouse_delta_x.abs() > 0.1 {
-            self.rotate(mouse_delta_x * MOUSE_SENSITIVITY);
+            self.rotate(mouse_delta_x * settings.mouse_sensitivity);
         }
     }
 }
 
-// Updated function that uses the new camera system with gamepad support
-pub fn process_events(player: &mut Player, rl: &RaylibHandle, maze: &Vec<Vec<char>>, gilrs: &mut Gilrs) {
-    // Use the new camera movement system
-    player.update_keyboard(rl, maze);
-    
-    // Only use mouse if cursor is hidden (FPS mode)
-    if rl.is_cursor_hidden() {
-        player.update_mouse(rl);
-    }
-    
-    // Update gamepad input
-    player.update_gamepad(gilrs, maze);
+// Farthest of delta, delta/2, delta/4... that fits, so a blocked move still closes in on the wall
+fn slide_axis(start: f32, delta: f32, fits: impl Fn(f32) -> bool) -> f32 {
+    let mut step = delta;
+    for _ in 0..CONTACT_STEPS {
+        if step == 0.0 {
+            break;
+        }
+        if fits(start + step) {
+            return start + step;
+        }
+        step /= 2.0;
+    }
+    start
 }
 
-fn is_position_valid(x: f32, y: f32, maze: &Vec<Vec<char>>, block_size: f32) -> bool {
-    // Convert world coordinates to maze coordinates
-    let maze_x = (x / block_size) as usize;
-    let maze_y = (y / block_size) as usize;
-    
-    // Check bounds
-    if maze_y >= maze.len() || maze_x >= maze[0].len() {
-        return false;
-    }
-    
-    // Check if the cell is empty (walkable)
-    // Allow walking on: empty spaces, goal, start, and any other non-wall characters
-    match maze[maze_y][maze_x] {
-        '+' | '-' | '|' => false,  // These are walls
-        _ => true,  // Everything else is walkable (including ' ', 'g', 's', etc.)
+// The player's circle overlaps no wall cell; anything outside the map counts as wall
+fn circle_fits(x: f32, y: f32, maze: &[Vec<char>], block_size: f32) -> bool {
+    let cells = |center: f32| {
+        let first = ((center - PLAYER_RADIUS) / block_size).floor() as i64;
+        let last = ((center + PLAYER_RADIUS) / block_size).floor() as i64;
+        first..=last
+    };
+    for row in cells(y) {
+        for col in cells(x) {
+            let cell = usize::try_from(row)
+                .ok()
+                .zip(usize::try_from(col).ok())
+                .and_then(|(row, col)| maze.get(row)?.get(col));
+            if cell.is_some_and(|&cell| !is_wall_cell(cell)) {
+                continue;
+            }
+            // Nearest point of the wall square to the center; touching is fine, overlapping isn't
+            let nearest_x = x.clamp(col as f32 * block_size, (col + 1) as f32 * block_size);
+            let nearest_y = y.clamp(row as f32 * block_size, (row + 1) as f32 * block_size);
+            if (x - nearest_x).powi(2) + (y - nearest_y).powi(2) < PLAYER_RADIUS * PLAYER_RADIUS {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Maze;
+
+    // Distance from the player's center to the nearest wall cell
+    fn clearance(player: &Player, maze: &[Vec<char>]) -> f32 {
+        let block = WORLD_BLOCK_SIZE as f32;
+        let mut nearest = f32::MAX;
+        for (row, cells) in maze.iter().enumerate() {
+            for (col, &cell) in cells.iter().enumerate() {
+                if is_wall_cell(cell) {
+                    let x = player.pos.x.clamp(col as f32 * block, (col + 1) as f32 * block);
+                    let y = player.pos.y.clamp(row as f32 * block, (row + 1) as f32 * block);
+                    nearest = nearest.min(((player.pos.x - x).powi(2) + (player.pos.y - y).powi(2)).sqrt());
+                }
+            }
+        }
+        nearest
+    }
+
+    #[test]
+    fn diagonal_into_a_wall_slides_along_it() {
+        let maze = Maze::from_rows(&["+------+", "|      |", "|      |", "+------+"]).map;
+        // Heading north-east into the top wall
+        let mut player = Player::new(Vector2::new(30.0, 30.0), -PI / 4.0, PI / 3.0);
+        for _ in 0..20 {
+            player.move_forward(3.0, &maze);
+        }
+        assert!(player.pos.x > 60.0, "se quedó pegado en x = {}", player.pos.x);
+        assert!((player.pos.y - (20.0 + PLAYER_RADIUS)).abs() < 0.1, "y = {}", player.pos.y);
+        assert!(clearance(&player, &maze) >= PLAYER_RADIUS);
+    }
+
+    #[test]
+    fn corners_stop_both_axes_without_clipping() {
+        let maze = Maze::from_rows(&["+----+", "|    |", "|    |", "+----+"]).map;
+        // Straight into the south-east inner corner
+        let mut player = Player::new(Vector2::new(30.0, 30.0), PI / 4.0, PI / 3.0);
+        for _ in 0..40 {
+            player.move_forward(3.0, &maze);
+        }
+        assert!((player.pos.x - (100.0 - PLAYER_RADIUS)).abs() < 0.1 && (player.pos.y - (60.0 - PLAYER_RADIUS)).abs() < 0.1);
+
+        // A wall corner poking into the path blocks the circle, not just the center point
+        let pillar = Maze::from_rows(&["+----+", "|    |", "| |  |", "|    |", "+----+"]).map;
+        // The pillar's top-left corner is (40, 40); (37, 37) is in an open cell but 4.2 units from it
+        assert!(!circle_fits(37.0, 37.0, &pillar, WORLD_BLOCK_SIZE as f32));
+        assert!(circle_fits(36.0, 36.0, &pillar, WORLD_BLOCK_SIZE as f32));
+    }
+
+    #[test]
+    fn narrow_corridors_can_be_walked_through() {
+        // One cell wide, with a bend
+        let maze = Maze::from_rows(&["+-----+", "|   | |", "+-+ | |", "  |   |", "  +---+"]).map;
+        let mut player = Player::new(Vector2::new(30.0, 30.0), 0.0, PI / 3.0);
+        let walk = |angle: f32, steps: usize, player: &mut Player| {
+            player.a = angle;
+            for _ in 0..steps {
+                player.move_forward(2.0, &maze);
+                assert!(clearance(player, &maze) >= PLAYER_RADIUS, "dentro de la pared en {:?}", player.pos);
+            }
+        };
+        walk(0.0, 30, &mut player); // East to the end of the top corridor
+        assert!((player.pos.x - (80.0 - PLAYER_RADIUS)).abs() < 0.1);
+        player.pos.x = 70.0; // Middle of the column that turns south
+        walk(PI / 2.0, 30, &mut player);
+        assert!((player.pos.y - (80.0 - PLAYER_RADIUS)).abs() < 0.1);
+        walk(0.0, 30, &mut player); // And east again along the bottom
+        assert!((player.pos.x - (120.0 - PLAYER_RADIUS)).abs() < 0.1);
+        assert_eq!((player.pos.y / 20.0) as usize, 3);
+    }
+
+    #[test]
+    fn interpolation_blends_steps_and_skips_teleports() {
+        let previous = Pose { pos: Vector2::new(30.0, 30.0), a: 2.0 * PI - 0.1 };
+        let player = Player::new(Vector2::new(34.0, 30.0), 0.1, PI / 3.0);
+        let halfway = player.interpolated(previous, 0.5);
+        assert!((halfway.pos.x - 32.0).abs() < 1e-4 && halfway.pos.y == 30.0);
+        assert!(halfway.a < 1e-4 || halfway.a > 2.0 * PI - 1e-4, "giró por el lado largo: {}", halfway.a);
+        assert_eq!(player.interpolated(previous, 1.0).pos, player.pos);
+
+        let respawned = Player::new(Vector2::new(130.0, 30.0), 0.1, PI / 3.0);
+        assert_eq!(respawned.interpolated(previous, 0.25).pos, respawned.pos);
+    }
+
+    #[test]
+    fn moves_off_the_map_are_rejected() {
+        let maze = Maze::from_rows(&["+--", "|  ", "+--"]).map;
+        let mut player = Player::new(Vector2::new(50.0, 30.0), 0.0, PI / 3.0);
+        player.move_by(30.0, 0.0, &maze);
+        assert!(player.pos.x + PLAYER_RADIUS <= 60.0, "x = {}", player.pos.x);
+    }
+
+    // A long open corridor heading east from (30, 30), so the player never reaches the end
+    fn open_field() -> (Vec<Vec<char>>, Player) {
+        let inner = " ".repeat(62);
+        let (edge, row) = (format!("+{}+", "-".repeat(62)), format!("|{}|", inner));
+        let maze = Maze::from_rows(&[&edge, &row, &row, &edge]).map;
+        (maze, Player::new(Vector2::new(30.0, 30.0), 0.0, PI / 3.0))
+    }
+
+    fn run(player: &mut Player, input: &Input, maze: &Vec<Vec<char>>, settings: &Settings, steps: usize) {
+        for _ in 0..steps {
+            player.update_actions(input, maze, settings, 1.0 / 120.0);
+        }
+    }
+
+    #[test]
+    fn speed_builds_up_to_the_top_and_friction_stops_it() {
+        let (maze, mut player) = open_field();
+        let settings = Settings::default();
+        let mut input = Input::new();
+        input.set(Action::MoveForward, 1.0);
+
+        run(&mut player, &input, &maze, &settings, 1);
+        assert!(player.velocity.x > 0.0 && player.velocity.x < settings.move_speed, "v = {}", player.velocity.x);
+        run(&mut player, &input, &maze, &settings, 60);
+        assert!((player.velocity.x - settings.move_speed).abs() < 1e-3, "v = {}", player.velocity.x);
+        assert!(player.velocity.y.abs() < 1e-3);
+
+        // Let go: it keeps gliding for a bit, then stops for good
+        input.set(Action::MoveForward, 0.0);
+        let released_at = player.pos.x;
+        run(&mut player, &input, &maze, &settings, 6);
+        assert!(player.velocity.x > 0.0 && player.pos.x > released_at);
+        run(&mut player, &input, &maze, &settings, 240);
+        assert_eq!(player.velocity, Vector2::zero());
+    }
+
+    #[test]
+    fn half_stick_walks_at_half_speed() {
+        let (maze, mut player) = open_field();
+        let settings = Settings::default();
+        let mut input = Input::new();
+        input.set(Action::MoveForward, 0.5);
+        run(&mut player, &input, &maze, &settings, 60);
+        assert!((player.velocity.length() - settings.move_speed / 2.0).abs() < 1e-3);
+
+        // Forward and strafe together are no faster than forward alone (heading east along the corridor)
+        player.a = -PI / 4.0;
+        input.set(Action::MoveForward, 1.0);
+        input.set(Action::StrafeRight, 1.0);
+        run(&mut player, &input, &maze, &settings, 60);
+        assert!((player.velocity.length() - settings.move_speed).abs() < 1e-3);
+    }
+
+    #[test]
+    fn sprinting_drains_stamina_until_exhausted() {
+        let (maze, mut player) = open_field();
+        let settings = Settings { stamina_seconds: 0.5, ..Settings::default() };
+        let mut input = Input::new();
+        input.set(Action::MoveForward, 1.0);
+        input.set(Action::Sprint, 1.0);
+
+        run(&mut player, &input, &maze, &settings, 30);
+        let sprint_speed = settings.move_speed * settings.sprint_multiplier;
+        assert!((player.velocity.x - sprint_speed).abs() < 1e-2, "v = {}", player.velocity.x);
+        assert!(player.stamina < 1.0);
+
+        // Out of breath: back down to walking speed, and no sprint until the meter refills enough
+        let mut steps = 30;
+        while player.can_sprint() && steps < 120 {
+            run(&mut player, &input, &maze, &settings, 1);
+            steps += 1;
+        }
+        assert!((58..=62).contains(&steps), "agotado tras {} pasos", steps);
+        run(&mut player, &input, &maze, &settings, 30);
+        assert!((player.velocity.x - settings.move_speed).abs() < 1e-2, "v = {}", player.velocity.x);
+        let seconds_to_recover = STAMINA_TO_SPRINT / STAMINA_RECOVERY;
+        run(&mut player, &input, &maze, &settings, (seconds_to_recover * 120.0) as usize - 32);
+        assert!(!player.can_sprint());
+        run(&mut player, &input, &maze, &settings, 4);
+        assert!(player.can_sprint());
+    }
+
+    #[test]
+    fn walls_take_the_velocity_pushing_into_them() {
+        let (maze, mut player) = open_field();
+        let settings = Settings::default();
+        let mut input = Input::new();
+        input.set(Action::MoveForward, 1.0);
+        input.set(Action::StrafeRight, 1.0); // South-east, into the bottom wall
+        run(&mut player, &input, &maze, &settings, 60);
+        assert!((player.pos.y - (60.0 - PLAYER_RADIUS)).abs() < 0.1, "y = {}", player.pos.y);
+        assert!(player.velocity.y.abs() < 1e-3, "vy = {}", player.velocity.y);
+        assert!(player.velocity.x > 0.0);
     }
 }
@@ -0,0 +1,184 @@
+//! Carrera local a pantalla dividida para dos jugadores.
+//!
+//! Los dos salen de la misma 'p' del mismo laberinto. Cada uno se mueve con su mitad del
+//! teclado (`race_bindings` en `settings.json`) y con su propio gamepad (el primero conectado para el jugador 1, el segundo para
+//! el jugador 2), y se ve en su mitad de la pantalla. Gana el primero que pisa 'g'.
+
+use raylib::prelude::*;
+
+use crate::framebuffer::{Framebuffer, RACER_COLORS};
+use crate::gamepad::Gamepads;
+use crate::input::{Bindings, Input};
+use crate::maze::{is_goal_at, Maze};
+use crate::player::{Player, Pose};
+use crate::render::{render_3d, Scene};
+use crate::settings::Settings;
+use crate::textures::TextureManager;
+
+pub const RACERS: usize = 2;
+
+pub struct Race {
+    pub players: Vec<Player>,
+    previous: Vec<Pose>,     // Each player at the previous simulation step, for interpolated drawing
+    inputs: Vec<Input>,
+    bindings: Vec<Bindings>, // Keyboard half and pad controls of each player
+    views: Vec<Framebuffer>, // One 3D view per player, pasted side by side
+    pub winner: Option<usize>,
+}
+
+impl Race {
+    pub fn new(maze: &Maze, settings: &Settings) -> Self {
+        let (pos, a) = maze.spawn_pose();
+        Race {
+            players: (0..RACERS).map(|_| Player::new(pos, a, settings.fov_radians())).collect(),
+            previous: vec![Pose { pos, a }; RACERS],
+            inputs: (0..RACERS).map(|_| Input::new()).collect(),
+            bindings: (0..RACERS).map(|racer| settings.bindings.for_racer(&settings.race_keys[racer])).collect(),
+            views: (0..RACERS).map(|_| Framebuffer::new(1, 1)).collect(),
+            winner: None,
+        }
+    }
+
+    /// Teclas de cada jugador para la barra de ayuda: `"J1: W/S/A/D + Q/E | J2: ..."`.
+    pub fn key_hint(&self) -> String {
+        let hints: Vec<String> = self.bindings.iter().enumerate().map(|(racer, keys)| format!("J{}: {}", racer + 1, keys.key_hint())).collect();
+        hints.join(" | ")
+    }
+
+    /// Lee los controles de cada jugador, una vez por frame.
+    pub fn read_input(&mut self, rl: &RaylibHandle, gamepads: &Gamepads, deadzone: f32) {
+        for (racer, input) in self.inputs.iter_mut().enumerate() {
+            input.update(rl, &gamepads.only(racer), &self.bindings[racer], deadzone);
+        }
+    }
+
+    /// Un paso de simulación de `dt` segundos; con la carrera decidida ya nadie se mueve.
+    pub fn step(&mut self, maze: &Vec<Vec<char>>, settings: &Settings, dt: f32) {
+        for ((player, previous), input) in self.players.iter_mut().zip(&mut self.previous).zip(&self.inputs) {
+            *previous = player.pose();
+            if self.winner.is_none() {
+                player.update_actions(input, maze, settings, dt);
+            }
+        }
+    }
+
+    /// Primer jugador sobre 'g'; el resultado ya no cambia. Si llegan en el mismo frame gana el jugador 1.
+    pub fn check_finish(&mut self, maze: &[Vec<char>]) -> Option<usize> {
+        if self.winner.is_none() {
+            self.winner = self.players.iter().position(|player| is_goal_at(maze, player.pos));
+        }
+        self.winner
+    }
+
+    /// Dibuja la vista 3D de cada jugador en su mitad del framebuffer, separadas por una franja.
+    /// `alpha` es la fracción del paso de simulación ya transcurrida (ver `timestep.rs`).
+    pub fn render(&mut self, framebuffer: &mut Framebuffer, maze: &Vec<Vec<char>>, texture_manager: &TextureManager, scene: &Scene, alpha: f32) {
+        let half = framebuffer.width / RACERS as u32;
+        for (racer, (player, view)) in self.players.iter().zip(&mut self.views).enumerate() {
+            // The last view takes the leftover column of an odd width
+            let width = if racer + 1 == RACERS { framebuffer.width - half * racer as u32 } else { half };
+            view.resize(width, framebuffer.height);
+            view.set_render_threads(framebuffer.render_threads());
+            let player = player.interpolated(self.previous[racer], alpha);
+            render_3d(view, &player, maze, texture_manager, scene);
+            framebuffer.blit(view, half * racer as u32, 0);
+        }
+
+        // Each half's edge in its player's color
+        for racer in 1..RACERS as u32 {
+            let x = half * racer;
+            for y in 0..framebuffer.height {
+                framebuffer.set_pixel_with_color(x - 1, y, RACER_COLORS[racer as usize - 1]);
+                framebuffer.set_pixel_with_color(x, y, RACER_COLORS[racer as usize]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framebuffer::rgba;
+    use crate::npc::Npc;
+    use crate::sprites::{SpriteManager, SpriteSheet};
+    use crate::textures::SurfaceStyle;
+    use raylib::prelude::Vector2;
+
+    const ROWS: [&str; 4] = ["+------+", "|p    g|", "|      |", "+------+"];
+
+    // Plain floor, no Pokémon and no enemies
+    fn with_scene<R>(draw: impl FnOnce(&TextureManager, &Scene) -> R) -> R {
+        let textures = TextureManager::headless();
+        let surfaces = SurfaceStyle { floor: "wall3.jpg".to_string(), ceiling: None };
+        let sprites = SpriteManager::with_sheet(SpriteSheet::procedural());
+        let npcs: [Npc; 0] = [];
+        draw(&textures, &Scene { surfaces: &surfaces, breadcrumbs: &[], sprites: &sprites, npcs: &npcs })
+    }
+
+    #[test]
+    fn racers_start_together_and_first_on_goal_wins() {
+        let maze = Maze::from_rows(&ROWS);
+        let mut race = Race::new(&maze, &Settings::default());
+        assert_eq!(race.players[0].pos, race.players[1].pos);
+        assert_eq!(race.check_finish(&maze.map), None);
+
+        race.players[1].pos = Vector2::new(130.0, 30.0); // Inside 'g'
+        assert_eq!(race.check_finish(&maze.map), Some(1));
+
+        // Arriving later doesn't steal the win
+        race.players[0].pos = race.players[1].pos;
+        race.players[1].pos = Vector2::new(30.0, 30.0);
+        assert_eq!(race.check_finish(&maze.map), Some(1));
+    }
+
+    #[test]
+    fn each_half_shows_its_own_player() {
+        let maze = Maze::from_rows(&ROWS);
+        with_scene(|textures, scene| {
+            let mut race = Race::new(&maze, &Settings::default());
+            race.players[1].a += std::f32::consts::PI; // Looking at the other end of the corridor
+            let mut framebuffer = Framebuffer::new(64, 40);
+            framebuffer.set_render_threads(1);
+            race.render(&mut framebuffer, &maze.map, textures, scene, 1.0);
+
+            for (racer, player) in race.players.iter().enumerate() {
+                let mut alone = Framebuffer::new(32, 40);
+                alone.set_render_threads(1);
+                render_3d(&mut alone, &player.interpolated(race.previous[racer], 1.0), &maze.map, textures, scene);
+                // Everything but the divider columns next to the middle
+                let columns = if racer == 0 { 0..31 } else { 1..32 };
+                for y in 0..40 {
+                    for x in columns.clone() {
+                        let (split, alone) = (rgba(framebuffer.get_pixel(32 * racer as u32 + x, y)), rgba(alone.get_pixel(x, y)));
+                        assert_eq!(split, alone, "jugador {} ({}, {})", racer + 1, x, y);
+                    }
+                }
+            }
+            assert_eq!(rgba(framebuffer.get_pixel(31, 0)), rgba(RACER_COLORS[0]));
+            assert_eq!(rgba(framebuffer.get_pixel(32, 39)), rgba(RACER_COLORS[1]));
+        });
+    }
+
+    #[test]
+    fn odd_widths_leave_no_stale_column() {
+        let maze = Maze::from_rows(&ROWS);
+        with_scene(|textures, scene| {
+            let mut race = Race::new(&maze, &Settings::default());
+            race.players[1].a += std::f32::consts::PI;
+            let mut framebuffer = Framebuffer::new(65, 40);
+            framebuffer.set_render_threads(1);
+            let stale = Color::new(1, 2, 3, 255);
+            for y in 0..40 {
+                framebuffer.set_pixel_with_color(64, y, stale);
+            }
+            race.render(&mut framebuffer, &maze.map, textures, scene, 1.0);
+
+            let mut alone = Framebuffer::new(33, 40);
+            alone.set_render_threads(1);
+            render_3d(&mut alone, &race.players[1].interpolated(race.previous[1], 1.0), &maze.map, textures, scene);
+            for y in 0..40 {
+                assert_eq!(rgba(framebuffer.get_pixel(64, y)), rgba(alone.get_pixel(32, y)), "fila {}", y);
+            }
+        });
+    }
+}
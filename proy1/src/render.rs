@@ -1,8 +1,19 @@
 use raylib::prelude::*;
-use crate::framebuffer::Framebuffer;
+use crate::framebuffer::{rgba, Framebuffer};
 use crate::player::Player;
-use crate::textures::TextureManager;
-use crate::caster::{cast_ray, Intersect};
+use crate::textures::{SurfaceStyle, TextureCache, TextureManager};
+use crate::caster::{cast_ray, trace_ray, Intersect};
+use crate::line::line;
+use crate::solver::cell_center;
+use crate::maze::{is_wall_cell, BRICK_WALL, GOAL_DOOR, MOSSY_WALL, WORLD_BLOCK_SIZE};
+use crate::sprites::{AnimatedSprite, SpriteManager, SpriteSheet};
+use crate::npc::Npc;
+use std::f32::consts::PI;
+
+// Scales wall height: a wall at distance d is (half screen height / d) * this many pixels tall
+const DISTANCE_TO_PROJECTION_PLANE: f32 = 70.0;
+// Radius of a hint breadcrumb on the floor, in world units
+const BREADCRUMB_RADIUS: f32 = 2.5;
 
 // Sample a color from a texture at given UV coordinates
 fn sample_texture(texture: &Texture2D, u: f32, v: f32) -> Color {
@@ -44,6 +55,9 @@ fn cell_to_color(cell: char) -> Color {
         '+' => Color::BROWN,
         '-' => Color::GRAY,
         '|' => Color::DARKGRAY,
+        MOSSY_WALL => Color::DARKGREEN,
+        BRICK_WALL => Color::MAROON,
+        GOAL_DOOR => Color::ORANGE,
         'g' => Color::GOLD,          // Goal - gold color but walkable
         's' => Color::LIME,          // Start - lime color but walkable  
         _ => Color::new(200, 200, 200, 255), // Light gray for empty spaces
@@ -87,8 +101,11 @@ fn draw_cell(
 }
 
 pub fn render_2d(framebuffer: &mut Framebuffer, player: &Player, maze: &Vec<Vec<char>>) {
-    let block_size = 32; // 2D display block size (8x6 maze = 256x192 pixels) 
-    let world_block_size = 20; // Size used in the world coordinates
+    // 2D display block size: the largest square cell that fits the whole maze in the framebuffer
+    let rows = maze.len().max(1);
+    let cols = maze.iter().map(Vec::len).max().unwrap_or(0).max(1);
+    let block_size = (framebuffer.width as usize / cols).min(framebuffer.height as usize / rows).max(1);
+    let world_block_size = WORLD_BLOCK_SIZE;
     
     // Draw the maze
     for (row_index, row) in maze.iter().enumerate() {
@@ -144,99 +161,90 @@ pub fn render_2d(framebuffer: &mut Framebuffer, player: &Player, maze: &Vec<Vec<
         }
     }
 
-    framebuffer.set_current_color(Color::WHITESMOKE);
-
     // Create a scaled player for raycasting in 2D view
-    let scaled_player = Player {
-        pos: Vector2::new(scaled_player_x, scaled_player_y),
-        a: player.a,
-        fov: player.fov,
-    };
+    let scaled_player = Player::new(Vector2::new(scaled_player_x, scaled_player_y), player.a, player.fov);
 
     // draw what the player sees with scaled coordinates
     let num_rays = 10;
+    let max_distance = 100.0; // Limit ray length for 2D view
+    framebuffer.set_current_color(Color::WHITESMOKE);
     for i in 0..num_rays {
         let current_ray = i as f32 / num_rays as f32;
         let a = scaled_player.a - (scaled_player.fov / 2.0) + (scaled_player.fov * current_ray);
-        cast_ray_2d(framebuffer, &maze, &scaled_player, a, block_size);
+        let intersect = cast_ray(framebuffer, maze, &scaled_player, a, block_size, false);
+        let length = intersect.distance.min(max_distance);
+        let end = Vector2::new(
+            scaled_player.pos.x + a.cos() * length,
+            scaled_player.pos.y + a.sin() * length,
+        );
+        line(framebuffer, scaled_player.pos, end);
     }
 }
 
-// Special raycasting function for 2D view with scaled coordinates
-fn cast_ray_2d(
+// Everything in the 3D view besides the walls
+pub struct Scene<'a> {
+    pub surfaces: &'a SurfaceStyle, // Floor/ceiling of the current region
+    pub breadcrumbs: &'a [(usize, usize)],
+    pub sprites: &'a SpriteManager,
+    pub npcs: &'a [Npc],
+}
+
+pub fn render_3d(
     framebuffer: &mut Framebuffer,
-    maze: &Vec<Vec<char>>,
     player: &Player,
-    a: f32,
-    block_size: usize,
+    maze: &Vec<Vec<char>>,
+    texture_manager: &TextureManager,
+    scene: &Scene,
 ) {
-    let mut d = 0.0;
-    let max_distance = 100.0; // Limit ray length for 2D view
-
-    framebuffer.set_current_color(Color::WHITESMOKE);
-
-    loop {
-        let cos = d * a.cos();
-        let sin = d * a.sin();
-        let x = player.pos.x + cos;
-        let y = player.pos.y + sin;
-
-        let i = (x / block_size as f32) as usize;
-        let j = (y / block_size as f32) as usize;
-
-        if j >= maze.len() || i >= maze[0].len() || d > max_distance {
-            break;
-        }
-
-        if maze[j][i] == '+' || maze[j][i] == '-' || maze[j][i] == '|' {
-            break;
-        }
-
-        if x >= 0.0 && y >= 0.0 && (x as u32) < framebuffer.width && (y as u32) < framebuffer.height {
-            framebuffer.set_pixel(x as u32, y as u32);
-        }
-        
-        d += 0.5; // Smaller increment for smoother lines in 2D
-    }
-}
-
-pub fn render_3d(framebuffer: &mut Framebuffer, player: &Player, maze: &Vec<Vec<char>>, texture_manager: &TextureManager) {
+    let surfaces = scene.surfaces;
     let num_rays = framebuffer.width;
+    let height = framebuffer.height as usize;
     let hh = framebuffer.height as f32 / 2.0;  // precalculated half height
-    let world_block_size = 20; // Must match the block size used in player.rs and 2D rendering
-
-    framebuffer.set_current_color(Color::WHITESMOKE);
-
-    for i in 0..num_rays {
+    let world_block_size = WORLD_BLOCK_SIZE;
+    let block = world_block_size as f32;
+
+    // Floor and ceiling images for this region; fall back to the default caches
+    let floor_cache = texture_manager
+        .image_cache(&surfaces.floor)
+        .or(texture_manager.floor_texture_cache.as_ref());
+    let sky_cache = texture_manager.sky_texture_cache.as_ref();
+    let ceiling_cache = surfaces
+        .ceiling
+        .as_ref()
+        .and_then(|name| texture_manager.image_cache(name).or(sky_cache));
+
+    // A floor point at perpendicular distance d projects to row hh + floor_scale / d
+    let floor_scale = hh * DISTANCE_TO_PROJECTION_PLANE / 2.0;
+
+    // Columns are independent, so bands of them are rendered in parallel;
+    // each one returns its perpendicular distance to the wall for the sprite pass
+    let wall_depths = framebuffer.render_columns(|i, column| {
         let current_ray = i as f32 / num_rays as f32;
         let a = player.a - (player.fov / 2.0) + (player.fov * current_ray);
-        let intersect = cast_ray(framebuffer, &maze, &player, a, world_block_size, false);
+        let intersect = trace_ray(maze, player.pos, a, world_block_size);
 
-        // Calculate the height of the stake
-        let distance_to_wall = intersect.distance;
-        let distance_to_projection_plane = 70.0;
-        let stake_height = (hh / distance_to_wall) * distance_to_projection_plane;
+        // Perpendicular distance keeps straight walls straight and lines them up with the floor
+        let cos_relative = (a - player.a).cos();
+        let distance_to_wall = intersect.distance * cos_relative;
+        let stake_height = (hh / distance_to_wall) * DISTANCE_TO_PROJECTION_PLANE;
 
         // Calculate the position to draw the stake
-        let stake_top = (hh - (stake_height / 2.0)) as usize;
-        let stake_bottom = (hh + (stake_height / 2.0)) as usize;
+        let stake_top_f = hh - (stake_height / 2.0);
+        let stake_top = stake_top_f as usize;
+        let stake_bottom = ((hh + (stake_height / 2.0)) as usize).min(height);
+        let intensity = distance_shade(distance_to_wall);
 
         // Draw the wall column with texture
-        for y in stake_top..stake_bottom.min(framebuffer.height as usize) {
-            // Calculate texture coordinates
-            let wall_height = stake_bottom - stake_top;
-            let ty = if wall_height > 0 {
-                ((y - stake_top) as f32 / wall_height as f32).clamp(0.0, 1.0)
-            } else {
-                0.0
-            };
+        for (y, pixel) in column.iter_mut().enumerate().take(stake_bottom).skip(stake_top) {
+            // Texture row measured from the unclipped top so close walls don't squash
+            let ty = ((y as f32 - stake_top_f) / stake_height).clamp(0.0, 1.0);
             
             // Get wall color from texture
             let wall_color = match intersect.impact {
-                '+' | '-' | '|' => {
-                    // Use wall texture with proper coordinates
+                wall if is_wall_cell(wall) => {
+                    // Each wall char has its own texture
                     let safe_tx = intersect.tx.clamp(0.0, 1.0);
-                    texture_manager.get_wall_texture_pixel(safe_tx, ty)
+                    texture_manager.get_wall_texture_pixel(wall, safe_tx, ty)
                 },
                 _ => {
                     // Fallback for non-wall cells
@@ -248,41 +256,196 @@ pub fn render_3d(framebuffer: &mut Framebuffer, player: &Player, maze: &Vec<Vec<
                 }
             };
 
-            // Apply distance shading
-            let intensity = 1.0 / (1.0 + distance_to_wall * distance_to_wall * 0.0001);
-            let intensity = intensity.min(1.0).max(0.3); // Keep minimum visibility
-            
-            let shaded_color = Color::new(
-                (wall_color.r as f32 * intensity) as u8,
-                (wall_color.g as f32 * intensity) as u8,
-                (wall_color.b as f32 * intensity) as u8,
-                255
-            );
-
-            framebuffer.set_current_color(shaded_color);
-            framebuffer.set_pixel(i, y as u32);
+            *pixel = rgba(shade(wall_color, intensity));
         }
 
-        // Draw sky with texture
-        for y in 0..stake_top {
-            let sky_u = i as f32 / framebuffer.width as f32;
-            let sky_v = y as f32 / stake_top.max(1) as f32;
-            
-            let sky_color = texture_manager.get_sky_texture_pixel(sky_u, sky_v);
-            framebuffer.set_current_color(sky_color);
-            framebuffer.set_pixel(i, y as u32);
+        // World-space step per unit of perpendicular distance along this column's ray
+        let ray_x = a.cos() / cos_relative;
+        let ray_y = a.sin() / cos_relative;
+
+        // Ceiling: cast back into the world like the floor, or the panoramic sky when open
+        for (y, pixel) in column.iter_mut().enumerate().take(stake_top) {
+            let color = match ceiling_cache {
+                Some(cache) => {
+                    let row_distance = floor_scale / (hh - y as f32 - 0.5);
+                    let color = sample_world(cache, player, ray_x, ray_y, row_distance, block);
+                    shade(color, distance_shade(row_distance))
+                }
+                None => {
+                    // Fixed to the world heading so the sky turns with the camera
+                    let sky_u = (a / (2.0 * PI)).rem_euclid(1.0);
+                    let sky_v = y as f32 / hh;
+                    match sky_cache {
+                        Some(cache) => cache.sample(sky_u, sky_v),
+                        None => texture_manager.get_sky_texture_pixel(sky_u, sky_v),
+                    }
+                }
+            };
+            *pixel = rgba(color);
         }
 
-        // Draw floor with texture
-        for y in stake_bottom..framebuffer.height as usize {
-            let floor_u = i as f32 / framebuffer.width as f32;
-            let floor_v = (y - stake_bottom) as f32 / (framebuffer.height as usize - stake_bottom).max(1) as f32;
-            
-            let floor_color = texture_manager.get_floor_texture_pixel(floor_u, floor_v);
-            framebuffer.set_current_color(floor_color);
-            framebuffer.set_pixel(i, y as u32);
+        // Floor: project each row back onto the ground plane
+        for (y, pixel) in column.iter_mut().enumerate().skip(stake_bottom) {
+            let row_distance = floor_scale / (y as f32 + 0.5 - hh);
+            let color = match floor_cache {
+                Some(cache) => sample_world(cache, player, ray_x, ray_y, row_distance, block),
+                None => {
+                    let (u, v) = world_uv(player, ray_x, ray_y, row_distance, block);
+                    texture_manager.get_floor_texture_pixel(u, v)
+                }
+            };
+            *pixel = rgba(shade(color, distance_shade(row_distance)));
+        }
+
+        distance_to_wall
+    });
+
+    draw_floor_breadcrumbs(framebuffer, player, scene.breadcrumbs, &wall_depths, block, hh);
+    let billboards = scene.sprites.sprites.iter().chain(scene.npcs.iter().map(|npc| &npc.sprite));
+    draw_sprites(framebuffer, player, &scene.sprites.sheet, billboards, &wall_depths, block, hh);
+}
+
+// One texture tile per map cell, anchored to the world
+fn sample_world(cache: &TextureCache, player: &Player, ray_x: f32, ray_y: f32, distance: f32, block: f32) -> Color {
+    let (u, v) = world_uv(player, ray_x, ray_y, distance, block);
+    cache.sample(u, v)
+}
+
+// Position inside its map cell, in [0, 1), of the floor or ceiling point `distance` along the ray
+fn world_uv(player: &Player, ray_x: f32, ray_y: f32, distance: f32, block: f32) -> (f32, f32) {
+    let world_x = player.pos.x + ray_x * distance;
+    let world_y = player.pos.y + ray_y * distance;
+    ((world_x / block).rem_euclid(1.0), (world_y / block).rem_euclid(1.0))
+}
+
+// Farther surfaces get darker, never below 30%
+fn distance_shade(distance: f32) -> f32 {
+    let intensity = 1.0 / (1.0 + distance * distance * 0.0001);
+    intensity.clamp(0.3, 1.0)
+}
+
+fn shade(color: Color, intensity: f32) -> Color {
+    Color::new(
+        (color.r as f32 * intensity) as u8,
+        (color.g as f32 * intensity) as u8,
+        (color.b as f32 * intensity) as u8,
+        255
+    )
+}
+
+// Angle of a world point relative to the view direction (wrapped to [-PI, PI]) and its perpendicular distance
+fn view_space(player: &Player, point: Vector2) -> (f32, f32) {
+    let dx = point.x - player.pos.x;
+    let dy = point.y - player.pos.y;
+    let relative = (dy.atan2(dx) - player.a + PI).rem_euclid(2.0 * PI) - PI;
+    (relative, (dx * dx + dy * dy).sqrt() * relative.cos())
+}
+
+// Billboards standing on the floor, far to near, clipped per column against the walls
+fn draw_sprites<'a>(
+    framebuffer: &mut Framebuffer,
+    player: &Player,
+    sheet: &SpriteSheet,
+    sprites: impl Iterator<Item = &'a AnimatedSprite>,
+    wall_depths: &[f32],
+    block_size: f32,
+    hh: f32,
+) {
+    let width = framebuffer.width as f32;
+    let height = framebuffer.height as f32;
+
+    let mut visible: Vec<(f32, f32, &AnimatedSprite)> = sprites
+        .map(|sprite| {
+            let (relative, distance) = view_space(player, sprite.position);
+            (relative, distance, sprite)
+        })
+        // Small margin so sprites half inside the view aren't popped out at the edges
+        .filter(|&(relative, distance, _)| distance > 1.0 && relative.abs() < player.fov / 2.0 + 0.3)
+        .collect();
+    visible.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    for (relative, distance, sprite) in visible {
+        let stake_height = (hh / distance) * DISTANCE_TO_PROJECTION_PLANE;
+        let sprite_height = sprite.size * stake_height / block_size;
+        let screen_x = (relative + player.fov / 2.0) / player.fov * width;
+        let bottom = hh + stake_height / 2.0;
+        let top = bottom - sprite_height;
+        let left = screen_x - sprite_height / 2.0;
+        let frame = sprite.frame();
+        let intensity = distance_shade(distance);
+
+        let x0 = left.max(0.0) as i32;
+        let x1 = (left + sprite_height).min(width) as i32;
+        let y0 = top.max(0.0) as i32;
+        let y1 = bottom.min(height) as i32;
+        for x in x0..x1 {
+            if wall_depths.get(x as usize).is_some_and(|&depth| depth < distance) {
+                continue;
+            }
+            let u = (x as f32 - left) / sprite_height;
+            for y in y0..y1 {
+                let v = (y as f32 - top) / sprite_height;
+                if let Some(color) = sheet.texel(frame, u, v) {
+                    framebuffer.set_pixel_with_color(x as u32, y as u32, shade(color, intensity));
+                }
+            }
         }
     }
 }
 
+// Hint trail drawn as flat glowing dots on the floor, hidden by walls in front of them
+fn draw_floor_breadcrumbs(
+    framebuffer: &mut Framebuffer,
+    player: &Player,
+    breadcrumbs: &[(usize, usize)],
+    wall_depths: &[f32],
+    block_size: f32,
+    hh: f32,
+) {
+    let width = framebuffer.width as f32;
+
+    // Farthest first so nearer crumbs overdraw them
+    for (step, &cell) in breadcrumbs.iter().enumerate().rev() {
+        let center = cell_center(cell, block_size);
+        let dx = center.x - player.pos.x;
+        let dy = center.y - player.pos.y;
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance < 1.0 {
+            continue;
+        }
 
+        // Angle relative to the view direction, wrapped to [-PI, PI]
+        let mut relative = dy.atan2(dx) - player.a;
+        relative = (relative + PI).rem_euclid(2.0 * PI) - PI;
+        if relative.abs() > player.fov / 2.0 {
+            continue;
+        }
+
+        // Same projection as the walls: the floor under a wall at this distance is its bottom edge
+        let distance = distance * relative.cos();
+        let stake_height = (hh / distance) * DISTANCE_TO_PROJECTION_PLANE;
+        let screen_x = (relative + player.fov / 2.0) / player.fov * width;
+        let screen_y = hh + stake_height / 2.0;
+        let radius_x = (BREADCRUMB_RADIUS * stake_height / block_size).max(1.0);
+        let radius_y = (radius_x * 0.4).max(1.0); // Flattened: it lies on the floor
+
+        // Nearer steps glow brighter
+        let fade = 1.0 - step as f32 / (breadcrumbs.len() as f32 + 1.0);
+        let color = Color::new(255, (180.0 + 60.0 * fade) as u8, (40.0 * fade) as u8, 255);
+
+        let x0 = (screen_x - radius_x).floor().max(0.0) as i32;
+        let x1 = (screen_x + radius_x).ceil().min(width - 1.0) as i32;
+        for x in x0..=x1 {
+            if wall_depths.get(x as usize).is_some_and(|&depth| depth < distance) {
+                continue;
+            }
+            let nx = (x as f32 - screen_x) / radius_x;
+            let half_height = radius_y * (1.0 - nx * nx).max(0.0).sqrt();
+            for y in (screen_y - half_height) as i32..=(screen_y + half_height) as i32 {
+                if y >= 0 {
+                    framebuffer.set_pixel_with_color(x as u32, y as u32, color);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,432 @@
+//! Ajustes del jugador, leídos de `settings.json` en la carpeta de configuración del usuario
+//! (`$XDG_CONFIG_HOME/proy1`, `~/.config/proy1` o `%APPDATA%\proy1`).
+//!
+//! Las claves que falten toman su valor por defecto y las desconocidas se ignoran:
+//!
+//! ```json
+//! { "window_width": 1280, "window_height": 720, "fullscreen": false, "render_scale": 0.5,
+//!   "mouse_sensitivity": 0.002, "move_speed_per_second": 180.0, "rotation_speed_per_second": 360.0,
+//!   "acceleration": 1200.0, "friction": 10.0, "sprint_multiplier": 1.6, "stamina_seconds": 3.0,
+//!   "fov": 60.0, "stick_deadzone": 0.1, "volume": 0.2 }
+//! ```
+//!
+//! Archivos anteriores al paso fijo guardaban `move_speed` y `rotation_speed` por frame a 60 FPS;
+//! se convierten al leerlos.
+//!
+//! Los de juego se editan en la pantalla de Ajustes y se guardan al salir de ella. Los controles
+//! de cada acción van bajo `"bindings"` (ver `input.rs`) y la mitad del teclado de cada jugador de
+//! la carrera bajo `"race_bindings"`, una lista con un objeto igual por jugador; ambos se editan en
+//! la pantalla de Controles.
+
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::audio::DEFAULT_VOLUME;
+use crate::input::Bindings;
+use crate::race::RACERS;
+
+pub const SETTINGS_FILE: &str = "settings.json";
+// Smallest window the menus still fit in
+pub const MIN_WINDOW_WIDTH: i32 = 320;
+pub const MIN_WINDOW_HEIGHT: i32 = 240;
+// The 3D view is rendered at this share of the window size at most/least, then stretched to fit
+const MIN_RENDER_SCALE: f32 = 0.25;
+const MAX_RENDER_SCALE: f32 = 1.0;
+// Frame rate the old per-frame speeds were tuned for
+const LEGACY_FPS: f32 = 60.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub window_width: i32,
+    pub window_height: i32,
+    pub fullscreen: bool,
+    pub render_scale: f32, // 0.5 = the framebuffer has half the window's width and height
+    pub mouse_sensitivity: f32, // Radians per pixel of mouse movement
+    pub move_speed: f32,        // Top walking speed, world units per second
+    pub rotation_speed: f32,    // Degrees per second with keys, sticks and triggers
+    pub acceleration: f32,      // World units per second squared towards the speed the input asks for
+    pub friction: f32,          // Share of the speed lost per second when coasting, as an exponential rate
+    pub sprint_multiplier: f32, // Top speed while sprinting, relative to move_speed
+    pub stamina_seconds: f32,   // Sprinting time with a full stamina meter
+    pub fov: f32,               // Degrees
+    pub stick_deadzone: f32,    // Stick deflection ignored around the center
+    pub volume: f32,            // Music volume, 0.0 to 1.0
+    pub bindings: Bindings,
+    pub race_keys: [Bindings; RACERS], // Keyboard half of each player in the split-screen race
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            window_width: 800,
+            window_height: 600,
+            fullscreen: false,
+            render_scale: 1.0,
+            mouse_sensitivity: 0.002,
+            move_speed: 180.0,     // 3 units per frame at 60 FPS
+            rotation_speed: 360.0, // PI / 30 per frame at 60 FPS
+            acceleration: 1200.0,  // Top speed in 0.15 s
+            friction: 10.0,
+            sprint_multiplier: 1.6,
+            stamina_seconds: 3.0,
+            fov: 60.0,           // PI / 3
+            stick_deadzone: 0.1,
+            volume: DEFAULT_VOLUME,
+            bindings: Bindings::default(),
+            race_keys: std::array::from_fn(Bindings::racer_keys),
+        }
+    }
+}
+
+/// Valores editables en la pantalla de Ajustes, en el orden en que se listan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    MouseSensitivity,
+    MoveSpeed,
+    RotationSpeed,
+    Acceleration,
+    Friction,
+    SprintMultiplier,
+    StaminaSeconds,
+    Fov,
+    StickDeadzone,
+    Volume,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 10] = [
+        Setting::MouseSensitivity,
+        Setting::MoveSpeed,
+        Setting::RotationSpeed,
+        Setting::Acceleration,
+        Setting::Friction,
+        Setting::SprintMultiplier,
+        Setting::StaminaSeconds,
+        Setting::Fov,
+        Setting::StickDeadzone,
+        Setting::Volume,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Setting::MouseSensitivity => "Sensibilidad del mouse",
+            Setting::MoveSpeed => "Velocidad máxima",
+            Setting::RotationSpeed => "Velocidad de giro",
+            Setting::Acceleration => "Aceleración",
+            Setting::Friction => "Fricción",
+            Setting::SprintMultiplier => "Velocidad al correr",
+            Setting::StaminaSeconds => "Resistencia",
+            Setting::Fov => "Campo de visión",
+            Setting::StickDeadzone => "Zona muerta del stick",
+            Setting::Volume => "Volumen",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Setting::MouseSensitivity => "mouse_sensitivity",
+            Setting::MoveSpeed => "move_speed_per_second",
+            Setting::RotationSpeed => "rotation_speed_per_second",
+            Setting::Acceleration => "acceleration",
+            Setting::Friction => "friction",
+            Setting::SprintMultiplier => "sprint_multiplier",
+            Setting::StaminaSeconds => "stamina_seconds",
+            Setting::Fov => "fov",
+            Setting::StickDeadzone => "stick_deadzone",
+            Setting::Volume => "volume",
+        }
+    }
+
+    // (min, max, step) for the settings screen; loaded values are clamped to the same range
+    fn range(self) -> (f32, f32, f32) {
+        match self {
+            Setting::MouseSensitivity => (0.0005, 0.01, 0.0005),
+            Setting::MoveSpeed => (60.0, 480.0, 30.0),
+            Setting::RotationSpeed => (60.0, 900.0, 30.0),
+            Setting::Acceleration => (200.0, 4000.0, 100.0),
+            Setting::Friction => (1.0, 30.0, 1.0),
+            Setting::SprintMultiplier => (1.0, 3.0, 0.1),
+            Setting::StaminaSeconds => (0.5, 10.0, 0.5),
+            Setting::Fov => (45.0, 110.0, 5.0),
+            Setting::StickDeadzone => (0.0, 0.5, 0.05),
+            Setting::Volume => (0.0, 1.0, 0.1),
+        }
+    }
+}
+
+impl Settings {
+    /// Lee los ajustes guardados; si no hay archivo o no se entiende, usa los de por defecto.
+    pub fn load() -> Self {
+        match config_dir() {
+            Some(dir) => Settings::load_from(&dir.join(SETTINGS_FILE)),
+            None => Settings::default(),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(contents) => Settings::from_json(&contents).unwrap_or_else(|e| {
+                println!("⚠️ {} no es válido ({}). Usando ajustes por defecto.", path.display(), e);
+                Settings::default()
+            }),
+            Err(_) => Settings::default(),
+        }
+    }
+
+    /// Guarda los ajustes en la carpeta de configuración; devuelve la ruta del archivo.
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let path = config_dir().ok_or("No se encontró la carpeta de configuración")?.join(SETTINGS_FILE);
+        self.save_to(&path)?;
+        Ok(path)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("No se pudo crear {}: {}", dir.display(), e))?;
+        }
+        fs::write(path, self.to_json()).map_err(|e| format!("No se pudo escribir {}: {}", path.display(), e))
+    }
+
+    pub fn from_json(contents: &str) -> Result<Self, String> {
+        let value: Value = serde_json::from_str(contents).map_err(|e| format!("Failed to parse JSON: {}", e))?;
+        let fields = value.as_object().ok_or("Se esperaba un objeto de ajustes")?;
+        let defaults = Settings::default();
+        let int = |key: &str, default: i32| fields.get(key).and_then(Value::as_i64).map_or(default, |v| v as i32);
+
+        let mut settings = Settings {
+            window_width: int("window_width", defaults.window_width).max(MIN_WINDOW_WIDTH),
+            window_height: int("window_height", defaults.window_height).max(MIN_WINDOW_HEIGHT),
+            fullscreen: fields.get("fullscreen").and_then(Value::as_bool).unwrap_or(defaults.fullscreen),
+            render_scale: fields
+                .get("render_scale")
+                .and_then(Value::as_f64)
+                .map_or(defaults.render_scale, |scale| (scale as f32).clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE)),
+            bindings: fields.get("bindings").map(Bindings::from_json).transpose()?.unwrap_or_default(),
+            ..defaults
+        };
+        if let Some(race) = fields.get("race_bindings") {
+            let race = race.as_array().ok_or("\"race_bindings\" debe ser una lista")?;
+            // Players missing from the list keep their default half
+            for (racer, value) in race.iter().enumerate().take(RACERS) {
+                settings.race_keys[racer] = Bindings::from_json_over(value, Bindings::racer_keys(racer))?;
+            }
+        }
+        for setting in Setting::ALL {
+            if let Some(value) = fields.get(setting.key()).and_then(Value::as_f64) {
+                let (min, max, _) = setting.range();
+                *settings.value_mut(setting) = (value as f32).clamp(min, max);
+            }
+        }
+        // Per-frame speeds saved before the fixed timestep, unless the file also has the new keys
+        for (key, setting) in [("move_speed", Setting::MoveSpeed), ("rotation_speed", Setting::RotationSpeed)] {
+            if !fields.contains_key(setting.key())
+                && let Some(value) = fields.get(key).and_then(Value::as_f64)
+            {
+                let (min, max, _) = setting.range();
+                *settings.value_mut(setting) = (value as f32 * LEGACY_FPS).clamp(min, max);
+            }
+        }
+        Ok(settings)
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut fields = serde_json::Map::new();
+        fields.insert("window_width".to_string(), Value::from(self.window_width));
+        fields.insert("window_height".to_string(), Value::from(self.window_height));
+        fields.insert("fullscreen".to_string(), Value::from(self.fullscreen));
+        fields.insert("render_scale".to_string(), number(self.render_scale));
+        for setting in Setting::ALL {
+            fields.insert(setting.key().to_string(), number(self.get(setting)));
+        }
+        fields.insert("bindings".to_string(), self.bindings.to_json());
+        fields.insert("race_bindings".to_string(), self.race_keys.iter().map(Bindings::to_json).collect());
+        serde_json::to_string_pretty(&Value::Object(fields)).unwrap_or_default()
+    }
+
+    /// Tamaño del framebuffer para una ventana de `width` x `height`: la escala de render, al menos 1x1.
+    pub fn render_size(&self, width: i32, height: i32) -> (u32, u32) {
+        let scaled = |side: i32| ((side as f32 * self.render_scale).round() as u32).max(1);
+        (scaled(width), scaled(height))
+    }
+
+    pub fn get(&self, setting: Setting) -> f32 {
+        match setting {
+            Setting::MouseSensitivity => self.mouse_sensitivity,
+            Setting::MoveSpeed => self.move_speed,
+            Setting::RotationSpeed => self.rotation_speed,
+            Setting::Acceleration => self.acceleration,
+            Setting::Friction => self.friction,
+            Setting::SprintMultiplier => self.sprint_multiplier,
+            Setting::StaminaSeconds => self.stamina_seconds,
+            Setting::Fov => self.fov,
+            Setting::StickDeadzone => self.stick_deadzone,
+            Setting::Volume => self.volume,
+        }
+    }
+
+    fn value_mut(&mut self, setting: Setting) -> &mut f32 {
+        match setting {
+            Setting::MouseSensitivity => &mut self.mouse_sensitivity,
+            Setting::MoveSpeed => &mut self.move_speed,
+            Setting::RotationSpeed => &mut self.rotation_speed,
+            Setting::Acceleration => &mut self.acceleration,
+            Setting::Friction => &mut self.friction,
+            Setting::SprintMultiplier => &mut self.sprint_multiplier,
+            Setting::StaminaSeconds => &mut self.stamina_seconds,
+            Setting::Fov => &mut self.fov,
+            Setting::StickDeadzone => &mut self.stick_deadzone,
+            Setting::Volume => &mut self.volume,
+        }
+    }
+
+    /// Sube (`steps` > 0) o baja un valor en pasos de la pantalla de Ajustes, sin salirse de su rango.
+    pub fn adjust(&mut self, setting: Setting, steps: i32) {
+        let (min, max, step) = setting.range();
+        let value = self.value_mut(setting);
+        // Snap to the step grid so repeated presses don't accumulate float error
+        *value = (((*value / step).round() + steps as f32) * step).clamp(min, max);
+    }
+
+    // Back to the defaults of the settings screen; window size, render scale and controls are kept
+    pub fn reset_gameplay(&mut self) {
+        let defaults = Settings::default();
+        for setting in Setting::ALL {
+            *self.value_mut(setting) = defaults.get(setting);
+        }
+    }
+
+    // Value as shown on the settings screen
+    pub fn display(&self, setting: Setting) -> String {
+        let value = self.get(setting);
+        match setting {
+            Setting::MouseSensitivity => format!("{:.4}", value),
+            Setting::MoveSpeed => format!("{:.0} u/s", value),
+            Setting::RotationSpeed => format!("{:.0}°/s", value),
+            Setting::Acceleration => format!("{:.0} u/s²", value),
+            Setting::Friction => format!("{:.0}/s", value),
+            Setting::SprintMultiplier => format!("x{:.1}", value),
+            Setting::StaminaSeconds => format!("{:.1} s", value),
+            Setting::Fov => format!("{:.0}°", value),
+            Setting::StickDeadzone => format!("{:.2}", value),
+            Setting::Volume => format!("{:.0}%", value * 100.0),
+        }
+    }
+
+    pub fn fov_radians(&self) -> f32 {
+        self.fov.to_radians()
+    }
+}
+
+// Floats are written with 4 decimals so the file stays readable (0.002, not 0.0020000000949949026)
+fn number(value: f32) -> Value {
+    Value::from((value as f64 * 10_000.0).round() / 10_000.0)
+}
+
+/// Carpeta de configuración de este juego, según la plataforma.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("proy1"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Action, Binding};
+    use raylib::prelude::KeyboardKey;
+
+    #[test]
+    fn missing_keys_use_defaults_and_values_are_clamped() {
+        let settings = Settings::from_json(r#"{ "window_width": 1280, "render_scale": 4.0, "extra": 1 }"#).unwrap();
+        assert_eq!(settings.window_width, 1280);
+        assert_eq!(settings.window_height, Settings::default().window_height);
+        assert_eq!(settings.render_scale, MAX_RENDER_SCALE);
+
+        let tiny = Settings::from_json(r#"{ "window_width": 10, "window_height": 10, "render_scale": 0.0 }"#).unwrap();
+        assert_eq!((tiny.window_width, tiny.window_height), (MIN_WINDOW_WIDTH, MIN_WINDOW_HEIGHT));
+        assert_eq!(tiny.render_scale, MIN_RENDER_SCALE);
+
+        let gameplay = Settings::from_json(r#"{ "fov": 170, "volume": -1, "move_speed_per_second": 270 }"#).unwrap();
+        assert_eq!((gameplay.fov, gameplay.volume, gameplay.move_speed), (110.0, 0.0, 270.0));
+        assert_eq!(gameplay.mouse_sensitivity, Settings::default().mouse_sensitivity);
+
+        assert!(Settings::from_json("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let settings = Settings {
+            window_width: 1920,
+            window_height: 1080,
+            fullscreen: true,
+            render_scale: 0.5,
+            mouse_sensitivity: 0.0035,
+            fov: 90.0,
+            volume: 0.7,
+            ..Settings::default()
+        };
+        assert_eq!(Settings::from_json(&settings.to_json()).unwrap(), settings);
+        assert!(settings.to_json().contains("\"mouse_sensitivity\": 0.0035"));
+    }
+
+    #[test]
+    fn saved_file_is_loaded_back() {
+        let path = std::env::temp_dir().join("proy1_settings_test").join(SETTINGS_FILE);
+        let mut settings = Settings { move_speed: 330.0, stick_deadzone: 0.25, ..Settings::default() };
+        settings.bindings.add(Action::MoveForward, Binding::Key(KeyboardKey::KEY_Z));
+        settings.race_keys[1].add(Action::Sprint, Binding::Key(KeyboardKey::KEY_SPACE));
+        settings.save_to(&path).unwrap();
+        assert_eq!(Settings::load_from(&path), settings);
+        // A missing file is not an error, just the defaults
+        assert_eq!(Settings::load_from(&path.with_file_name("no_existe.json")), Settings::default());
+    }
+
+    #[test]
+    fn race_bindings_default_per_player() {
+        let only_first = Settings::from_json(r#"{ "race_bindings": [{ "TurnLeft": ["Key:Z"] }] }"#).unwrap();
+        assert_eq!(only_first.race_keys[0].get(Action::TurnLeft), [Binding::Key(KeyboardKey::KEY_Z)]);
+        assert_eq!(only_first.race_keys[0].get(Action::TurnRight), Bindings::racer_keys(0).get(Action::TurnRight));
+        assert_eq!(only_first.race_keys[1], Bindings::racer_keys(1));
+        assert!(Settings::from_json(r#"{ "race_bindings": {} }"#).is_err());
+    }
+
+    #[test]
+    fn per_frame_speeds_are_converted() {
+        let old = Settings::from_json(r#"{ "move_speed": 4.5, "rotation_speed": 3 }"#).unwrap();
+        assert_eq!((old.move_speed, old.rotation_speed), (270.0, 180.0));
+        // The per-second key wins when a file has both
+        let both = Settings::from_json(r#"{ "move_speed": 4.5, "move_speed_per_second": 120 }"#).unwrap();
+        assert_eq!(both.move_speed, 120.0);
+        assert!(!Settings::default().to_json().contains("\"move_speed\""));
+    }
+
+    #[test]
+    fn adjust_steps_within_range() {
+        let mut settings = Settings::default();
+        settings.adjust(Setting::Volume, 3);
+        assert_eq!(settings.display(Setting::Volume), "50%");
+        settings.adjust(Setting::Volume, 20);
+        assert_eq!(settings.volume, 1.0);
+        settings.adjust(Setting::Fov, -1);
+        assert_eq!(settings.display(Setting::Fov), "55°");
+        for _ in 0..30 {
+            settings.adjust(Setting::MouseSensitivity, -1);
+        }
+        assert_eq!(settings.mouse_sensitivity, 0.0005);
+
+        settings.render_scale = 0.5;
+        settings.reset_gameplay();
+        assert_eq!(settings, Settings { render_scale: 0.5, ..Settings::default() });
+    }
+
+    #[test]
+    fn render_size_follows_scale() {
+        let settings = Settings { render_scale: 0.5, ..Settings::default() };
+        assert_eq!(settings.render_size(1280, 720), (640, 360));
+        assert_eq!(settings.render_size(1, 1), (1, 1));
+    }
+}
@@ -0,0 +1,119 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use raylib::prelude::Vector2;
+
+use crate::maze::is_wall_cell;
+
+/// Camino más corto (A* con distancia Manhattan) entre dos celdas de `Maze::map`.
+///
+/// Devuelve las celdas en orden, incluyendo inicio y destino, o `None` si no hay camino.
+/// Las celdas son (columna, fila) y solo se avanza en las cuatro direcciones.
+pub fn find_path(map: &[Vec<char>], start: (usize, usize), goal: (usize, usize)) -> Option<Vec<(usize, usize)>> {
+    if !is_open(map, start) || !is_open(map, goal) {
+        return None;
+    }
+
+    let height = map.len();
+    let width = map.iter().map(|row| row.len()).max().unwrap_or(0);
+    let index = |(x, y): (usize, usize)| y * width + x;
+    let heuristic = |(x, y): (usize, usize)| x.abs_diff(goal.0) + y.abs_diff(goal.1);
+
+    let mut cost = vec![usize::MAX; width * height];
+    let mut came_from: Vec<Option<(usize, usize)>> = vec![None; width * height];
+    // Ties on f break on the cell position so the chosen path never depends on heap internals
+    let mut open = BinaryHeap::new();
+
+    cost[index(start)] = 0;
+    open.push(Reverse((heuristic(start), start.1, start.0)));
+
+    while let Some(Reverse((_, y, x))) = open.pop() {
+        let current = (x, y);
+        if current == goal {
+            let mut path = vec![goal];
+            while let Some(previous) = came_from[index(*path.last().unwrap())] {
+                path.push(previous);
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        let next_cost = cost[index(current)] + 1;
+        for next in neighbors(current) {
+            if is_open(map, next) && next_cost < cost[index(next)] {
+                cost[index(next)] = next_cost;
+                came_from[index(next)] = Some(current);
+                open.push(Reverse((next_cost + heuristic(next), next.1, next.0)));
+            }
+        }
+    }
+
+    None
+}
+
+// Shortest path from a world position to the 'g' cell
+pub fn path_to_goal(map: &[Vec<char>], pos: Vector2, block_size: f32) -> Option<Vec<(usize, usize)>> {
+    let goal = map
+        .iter()
+        .enumerate()
+        .find_map(|(y, row)| row.iter().position(|&c| c == 'g').map(|x| (x, y)))?;
+    find_path(map, world_to_cell(pos, block_size)?, goal)
+}
+
+pub fn world_to_cell(pos: Vector2, block_size: f32) -> Option<(usize, usize)> {
+    if pos.x < 0.0 || pos.y < 0.0 {
+        return None;
+    }
+    Some(((pos.x / block_size) as usize, (pos.y / block_size) as usize))
+}
+
+// World-space center of a cell, where breadcrumbs are placed
+pub fn cell_center((x, y): (usize, usize), block_size: f32) -> Vector2 {
+    Vector2::new((x as f32 + 0.5) * block_size, (y as f32 + 0.5) * block_size)
+}
+
+fn is_open(map: &[Vec<char>], (x, y): (usize, usize)) -> bool {
+    map.get(y).and_then(|row| row.get(x)).is_some_and(|&c| !is_wall_cell(c))
+}
+
+// Out-of-range neighbors wrap to usize::MAX and fail `is_open`
+fn neighbors((x, y): (usize, usize)) -> [(usize, usize); 4] {
+    [(x + 1, y), (x, y + 1), (x.wrapping_sub(1), y), (x, y.wrapping_sub(1))]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Maze;
+
+    #[test]
+    fn finds_shortest_path_around_walls() {
+        let map = Maze::from_rows(&["+-----+", "|p |  |", "|  | g|", "|     |", "+-----+"]).map;
+        let path = find_path(&map, (1, 1), (5, 2)).unwrap();
+        assert_eq!(path.first(), Some(&(1, 1)));
+        assert_eq!(path.last(), Some(&(5, 2)));
+        // Down two, right four, up one
+        assert_eq!(path.len(), 8);
+        for pair in path.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            assert_eq!(a.0.abs_diff(b.0) + a.1.abs_diff(b.1), 1);
+            assert!(!is_wall_cell(map[b.1][b.0]));
+        }
+    }
+
+    #[test]
+    fn no_path_through_solid_wall() {
+        let map = Maze::from_rows(&["+---+", "|p|g|", "+---+"]).map;
+        assert_eq!(find_path(&map, (1, 1), (3, 1)), None);
+        assert_eq!(find_path(&map, (0, 0), (3, 1)), None);
+    }
+
+    #[test]
+    fn path_from_world_position() {
+        let map = Maze::from_rows(&["+----+", "|p  g|", "+----+"]).map;
+        // Block size 20: x = 45 is inside cell 2
+        let path = path_to_goal(&map, Vector2::new(45.0, 30.0), 20.0).unwrap();
+        assert_eq!(path, [(2, 1), (3, 1), (4, 1)]);
+        assert_eq!(path_to_goal(&map, Vector2::new(-5.0, 30.0), 20.0), None);
+    }
+}
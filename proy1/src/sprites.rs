@@ -1,105 +1,324 @@
 use raylib::prelude::*;
 use std::time::{Duration, Instant};
 
+use crate::maze::{Maze, WORLD_BLOCK_SIZE};
+use crate::rng::Rng;
+use crate::solver::cell_center;
+use crate::validate::distances_from;
+use crate::textures::IMAGE_DIR;
+
+// Sprite sheet: one row per species, FRAMES_PER_SPECIES frames of FRAME_SIZE x FRAME_SIZE each
+pub const SPRITE_SHEET: &str = "pokemon_sprites.png";
+pub const SPECIES: [&str; 4] = ["Pikachu", "Bulbasaur", "Charmander", "Squirtle"];
+// Row after the collectibles, used by the wandering enemies
+pub const GASTLY: usize = SPECIES.len();
+const FRAME_SIZE: f32 = 32.0;
+const FRAMES_PER_SPECIES: usize = 2;
+
+// Background color that counts as transparent (besides real alpha)
+pub const ALPHA_KEY: (u8, u8, u8) = (255, 0, 255);
+
+// Sprite height in world units (walls are one block tall)
+const SPRITE_SIZE: f32 = 12.0;
+// Walking this close to a Pokémon captures it; half a corridor, so passing through its cell is enough
+const CAPTURE_RADIUS: f32 = 10.0;
+
+/// Píxeles de una hoja de sprites, listos para muestrear desde el renderizador.
+pub struct SpriteSheet {
+    width: usize,
+    pixels: Vec<Color>, // Row-major; frames are addressed in pixels so the sheet keeps its size
+}
+
+impl SpriteSheet {
+    pub fn load(path: &str) -> Option<Self> {
+        match Image::load_image(path) {
+            Ok(image) => {
+                println!("Hoja de sprites cargada: {}", path);
+                Some(SpriteSheet { width: image.width as usize, pixels: image.get_image_data().to_vec() })
+            },
+            Err(e) => {
+                println!("Warning: No se pudo cargar {}: {}. Usando sprites procedurales.", path, e);
+                None
+            }
+        }
+    }
+
+    // Fallback sheet with the same layout: a colored ball per species that bounces between frames
+    pub fn procedural() -> Self {
+        let colors = [Color::YELLOW, Color::new(90, 190, 160, 255), Color::ORANGE, Color::SKYBLUE, Color::PURPLE];
+        let size = FRAME_SIZE as usize;
+        let key = Color::new(ALPHA_KEY.0, ALPHA_KEY.1, ALPHA_KEY.2, 255);
+        let width = size * FRAMES_PER_SPECIES;
+        let mut pixels = vec![key; width * size * colors.len()];
+
+        for (row, color) in colors.iter().enumerate() {
+            for frame in 0..FRAMES_PER_SPECIES {
+                let center_y = size as f32 * 0.6 - frame as f32;
+                for y in 0..size {
+                    for x in 0..size {
+                        let dx = x as f32 - size as f32 / 2.0;
+                        let dy = y as f32 - center_y;
+                        if dx * dx + dy * dy <= (size as f32 * 0.35).powi(2) {
+                            pixels[(row * size + y) * width + frame * size + x] = *color;
+                        }
+                    }
+                }
+            }
+        }
+        SpriteSheet { width, pixels }
+    }
+
+    // Frame rectangles of one species, left to right
+    pub fn species_frames(species: usize) -> Vec<Rectangle> {
+        (0..FRAMES_PER_SPECIES)
+            .map(|frame| Rectangle::new(frame as f32 * FRAME_SIZE, species as f32 * FRAME_SIZE, FRAME_SIZE, FRAME_SIZE))
+            .collect()
+    }
+
+    /// Color en (u, v) ∈ [0, 1) dentro de `frame`, o `None` si el píxel es transparente.
+    /// Las coordenadas se quedan dentro del frame: en el borde derecho o inferior no se lee el vecino.
+    pub fn texel(&self, frame: &Rectangle, u: f32, v: f32) -> Option<Color> {
+        let last_x = (frame.x + frame.width).ceil() - 1.0;
+        let last_y = (frame.y + frame.height).ceil() - 1.0;
+        let x = (frame.x + u * frame.width).clamp(frame.x, last_x) as usize;
+        let y = (frame.y + v * frame.height).clamp(frame.y, last_y) as usize;
+        if x >= self.width {
+            return None;
+        }
+        let color = *self.pixels.get(y * self.width + x)?;
+        let keyed = (color.r, color.g, color.b) == ALPHA_KEY;
+        if keyed || color.a < 128 { None } else { Some(color) }
+    }
+}
+
 pub struct AnimatedSprite {
-    pub position: Vector2,
-    pub frames: Vec<Rectangle>,
+    pub position: Vector2,      // World position of the sprite's feet
+    pub frames: Vec<Rectangle>, // Frame rectangles inside the sprite sheet
     pub current_frame: usize,
     pub frame_duration: Duration,
     pub last_frame_time: Instant,
-    pub texture: Option<Texture2D>,
-    pub scale: f32,
-    pub color: Color,
+    pub size: f32,              // Height in world units
+    pub species: usize,         // Index into SPECIES
 }
 
 impl AnimatedSprite {
-    pub fn new(position: Vector2, frame_duration: Duration) -> Self {
+    pub fn new(position: Vector2, species: usize, frame_duration: Duration) -> Self {
         AnimatedSprite {
             position,
-            frames: vec![
-                Rectangle::new(0.0, 0.0, 32.0, 32.0),
-                Rectangle::new(32.0, 0.0, 32.0, 32.0),
-                Rectangle::new(64.0, 0.0, 32.0, 32.0),
-                Rectangle::new(96.0, 0.0, 32.0, 32.0),
-            ],
+            frames: SpriteSheet::species_frames(species),
             current_frame: 0,
             frame_duration,
             last_frame_time: Instant::now(),
-            texture: None,
-            scale: 2.0,
-            color: Color::WHITE,
+            size: SPRITE_SIZE,
+            species,
         }
     }
-    
+
     pub fn update(&mut self) {
         if self.last_frame_time.elapsed() >= self.frame_duration {
             self.current_frame = (self.current_frame + 1) % self.frames.len();
             self.last_frame_time = Instant::now();
         }
     }
-    
-    pub fn draw_as_pixels(&self, framebuffer: &mut crate::framebuffer::Framebuffer) {
-        // Dibujar sprite como píxeles en el framebuffer
-        let sprite_size = 8;
-        let colors = [
-            Color::YELLOW,  // Pikachu amarillo
-            Color::ORANGE,  // Naranja
-            Color::RED,     // Rojo
-            Color::YELLOW,  // Amarillo de nuevo
-        ];
-        
-        let color = colors[self.current_frame];
-        
-        // Dibujar un sprite simple de 8x8 píxeles
-        for y in 0..sprite_size {
-            for x in 0..sprite_size {
-                let pixel_x = (self.position.x as i32 + x - sprite_size / 2) as u32;
-                let pixel_y = (self.position.y as i32 + y - sprite_size / 2) as u32;
-                
-                if pixel_x < framebuffer.width && pixel_y < framebuffer.height {
-                    framebuffer.set_pixel(pixel_x, pixel_y, color);
-                }
-            }
-        }
+
+    pub fn frame(&self) -> &Rectangle {
+        &self.frames[self.current_frame]
     }
 }
 
 pub struct SpriteManager {
-    pub sprites: Vec<AnimatedSprite>,
+    pub sprites: Vec<AnimatedSprite>, // Pokémon still free in the maze
+    pub sheet: SpriteSheet,
+    total: usize,                     // Placed in this maze, captured or not
 }
 
 impl SpriteManager {
     pub fn new() -> Self {
-        let mut sprites = Vec::new();
-        
-        // Crear algunos sprites Pokémon animados en posiciones aleatorias
-        sprites.push(AnimatedSprite::new(
-            Vector2::new(200.0, 300.0),
-            Duration::from_millis(500)
-        ));
-        
-        sprites.push(AnimatedSprite::new(
-            Vector2::new(600.0, 200.0),
-            Duration::from_millis(400)
-        ));
-        
-        sprites.push(AnimatedSprite::new(
-            Vector2::new(400.0, 450.0),
-            Duration::from_millis(600)
-        ));
-        
-        SpriteManager { sprites }
-    }
-    
+        let path = format!("{}/{}", IMAGE_DIR, SPRITE_SHEET);
+        let sheet = SpriteSheet::load(&path).unwrap_or_else(SpriteSheet::procedural);
+        Self::with_sheet(sheet)
+    }
+
+    pub fn with_sheet(sheet: SpriteSheet) -> Self {
+        SpriteManager { sprites: Vec::new(), sheet, total: 0 }
+    }
+
+    /// Coloca `count` Pokémon en celdas libres del laberinto.
+    ///
+    /// Las posiciones salen de la semilla del laberinto, así que la misma semilla
+    /// repite también los sprites. Nunca se usan 'p' ni 'g' ni celdas contiguas
+    /// (tampoco junto a 'p', para que no se capturen al aparecer), ni celdas a las que
+    /// no se llega desde 'p'. Si no caben todos, se colocan los que entren.
+    pub fn place_in_maze(&mut self, maze: &Maze, count: usize) {
+        let mut rng = Rng::new(maze.seed ^ 0x5B1D_E5B1_7E5B_A11D);
+        let spawn = maze
+            .map
+            .iter()
+            .enumerate()
+            .find_map(|(y, row)| row.iter().position(|&c| c == 'p').map(|x| (x, y)));
+        // Hand-made levels can have sealed pockets; a Pokémon there would block a capture-all exit
+        let reachable = spawn.map(|spawn| distances_from(&maze.map, spawn));
+        let mut candidates: Vec<(usize, usize)> = maze
+            .map
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter().enumerate().filter(|&(_, &c)| c == ' ').map(move |(x, _)| (x, y)))
+            .filter(|&(x, y)| reachable.as_ref().is_none_or(|distances| distances[y][x].is_some()))
+            .collect();
+        rng.shuffle(&mut candidates);
+
+        let mut chosen: Vec<(usize, usize)> = Vec::new();
+        for (x, y) in candidates {
+            if chosen.len() == count {
+                break;
+            }
+            let far_enough = |&(cx, cy): &(usize, usize)| cx.abs_diff(x).max(cy.abs_diff(y)) >= 2;
+            if chosen.iter().all(far_enough) && spawn.iter().all(far_enough) {
+                chosen.push((x, y));
+            }
+        }
+
+        let block = WORLD_BLOCK_SIZE as f32;
+        self.sprites = chosen
+            .into_iter()
+            .enumerate()
+            .map(|(i, (x, y))| {
+                let position = cell_center((x, y), block);
+                let frame_ms = 350 + 50 * (i as u64 % 4);
+                AnimatedSprite::new(position, rng.gen_range(SPECIES.len()), Duration::from_millis(frame_ms))
+            })
+            .collect();
+        self.total = self.sprites.len();
+    }
+
+    /// Captura los Pokémon al alcance de `pos` y devuelve sus nombres.
+    pub fn capture_at(&mut self, pos: Vector2) -> Vec<&'static str> {
+        let mut captured = Vec::new();
+        self.sprites.retain(|sprite| {
+            let in_reach = sprite.position.distance_to(pos) <= CAPTURE_RADIUS;
+            if in_reach {
+                captured.push(SPECIES[sprite.species]);
+            }
+            !in_reach
+        });
+        captured
+    }
+
+    pub fn captured(&self) -> usize {
+        self.total - self.sprites.len()
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn all_captured(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
     pub fn update(&mut self) {
         for sprite in &mut self.sprites {
             sprite.update();
         }
     }
-    
-    pub fn draw(&self, framebuffer: &mut crate::framebuffer::Framebuffer) {
-        for sprite in &self.sprites {
-            sprite.draw_as_pixels(framebuffer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn maze(rows: &[&str], seed: u64) -> Maze {
+        Maze { seed, ..Maze::from_rows(rows) }
+    }
+
+    fn manager() -> SpriteManager {
+        SpriteManager::with_sheet(SpriteSheet::procedural())
+    }
+
+    const ROWS: [&str; 5] = ["+--------+", "|p       |", "|        |", "|       g|", "+--------+"];
+
+    #[test]
+    fn placement_is_open_spread_and_reproducible() {
+        let mut sprites = manager();
+        sprites.place_in_maze(&maze(&ROWS, 9), 4);
+        assert_eq!(sprites.total(), 4);
+
+        let cells: Vec<(usize, usize)> = sprites
+            .sprites
+            .iter()
+            .map(|s| ((s.position.x / 20.0) as usize, (s.position.y / 20.0) as usize))
+            .collect();
+        for (i, &(x, y)) in cells.iter().enumerate() {
+            assert_eq!(ROWS[y].as_bytes()[x], b' ');
+            assert!(x.max(y) >= 3, "demasiado cerca de 'p': {:?}", (x, y));
+            for &(ox, oy) in &cells[i + 1..] {
+                assert!(ox.abs_diff(x).max(oy.abs_diff(y)) >= 2);
+            }
+        }
+
+        let mut again = manager();
+        again.place_in_maze(&maze(&ROWS, 9), 4);
+        let positions = |m: &SpriteManager| m.sprites.iter().map(|s| s.position).collect::<Vec<_>>();
+        assert_eq!(positions(&again), positions(&sprites));
+    }
+
+    #[test]
+    fn places_what_fits() {
+        let mut sprites = manager();
+        sprites.place_in_maze(&maze(&["+----+", "|p  g|", "+----+"], 1), 10);
+        // Cell 2 touches 'p', so cell 3 is the only place left
+        assert_eq!(sprites.total(), 1);
+        assert_eq!(sprites.sprites[0].position, cell_center((3, 1), 20.0));
+    }
+
+    #[test]
+    fn sealed_pockets_get_no_pokemon() {
+        let rows = ["+-------+", "|p  |   |", "|g  |   |", "+-------+"];
+        for seed in 0..20 {
+            let mut sprites = manager();
+            sprites.place_in_maze(&maze(&rows, seed), 10);
+            assert!(sprites.total() > 0);
+            for sprite in &sprites.sprites {
+                assert!(sprite.position.x < 80.0, "semilla {}: Pokémon encerrado en {:?}", seed, sprite.position);
+            }
+        }
+    }
+
+    #[test]
+    fn walking_over_captures() {
+        let mut sprites = manager();
+        sprites.place_in_maze(&maze(&ROWS, 3), 2);
+        let target = sprites.sprites[0].position;
+
+        assert!(sprites.capture_at(Vector2::new(-100.0, -100.0)).is_empty());
+        assert_eq!(sprites.capture_at(target + Vector2::new(5.0, 0.0)).len(), 1);
+        assert_eq!((sprites.captured(), sprites.total()), (1, 2));
+        assert!(!sprites.all_captured());
+
+        let last = sprites.sprites[0].position;
+        sprites.capture_at(last);
+        assert!(sprites.all_captured());
+    }
+
+    #[test]
+    fn sampling_stays_inside_the_frame() {
+        // Frame 0 is all green, frame 1 all red
+        let size = FRAME_SIZE as usize;
+        let width = size * 2;
+        let pixels = (0..width * size).map(|i| if i % width < size { Color::GREEN } else { Color::RED }).collect();
+        let sheet = SpriteSheet { width, pixels };
+        let frame = SpriteSheet::species_frames(0)[0];
+        for (u, v) in [(1.0, 0.5), (0.999_999, 0.5), (0.5, 1.0), (1.0, 1.0), (-0.1, -0.1)] {
+            let color = sheet.texel(&frame, u, v).unwrap();
+            assert_eq!((color.r, color.g, color.b), (Color::GREEN.r, Color::GREEN.g, Color::GREEN.b), "({}, {})", u, v);
         }
     }
+
+    #[test]
+    fn magenta_and_clear_pixels_are_transparent() {
+        let sheet = SpriteSheet::procedural();
+        let frame = SpriteSheet::species_frames(0)[0];
+        assert!(sheet.texel(&frame, 0.0, 0.0).is_none());
+        assert!(sheet.texel(&frame, 0.5, 0.6).is_some());
+    }
 }
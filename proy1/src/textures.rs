@@ -1,66 +1,163 @@
 use raylib::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::maze::{BRICK_WALL, GOAL_DOOR, MOSSY_WALL};
+
+// Images are looked up by file name inside this directory
+pub const IMAGE_DIR: &str = "assets/img";
+
+// Texture of each wall char unless the region or level says otherwise.
+// Names without an extension are generated in code instead of read from IMAGE_DIR.
+pub const DEFAULT_WALL_TEXTURES: [(char, &str); 6] = [
+    ('+', "wall1.jpg"),
+    ('-', "wall2.jpg"),
+    ('|', "wall4.jpg"),
+    (MOSSY_WALL, "mossy"),
+    (BRICK_WALL, "brick"),
+    (GOAL_DOOR, "goal_door"),
+];
+
+// Side of the generated textures
+const PROCEDURAL_SIZE: usize = 64;
+// Larger images are scaled down to this when cached
+const MAX_CACHE_SIZE: usize = 512;
+
+/// Textura en memoria contigua con lados potencia de dos, para muestrear sin divisiones:
+/// el índice sale de una multiplicación y una máscara, y las coordenadas se repiten solas.
+#[derive(Debug, Clone)]
+pub struct TextureCache {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>, // Row-major
+}
+
+impl TextureCache {
+    pub fn from_fn(width: usize, height: usize, pixel: impl Fn(usize, usize) -> Color) -> Self {
+        assert!(width.is_power_of_two() && height.is_power_of_two(), "{}x{} no es potencia de dos", width, height);
+        let pixels = (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).map(|(x, y)| pixel(x, y)).collect();
+        TextureCache { width, height, pixels }
+    }
+
+    // Scale an image to the nearest power of two not above it (capped at MAX_CACHE_SIZE)
+    pub fn from_image(image: &Image) -> Self {
+        let fit = |side: i32| {
+            let side = side.max(1) as usize;
+            let power = if side.is_power_of_two() { side } else { side.next_power_of_two() / 2 };
+            power.min(MAX_CACHE_SIZE)
+        };
+        let (width, height) = (fit(image.width), fit(image.height));
+
+        let mut scaled = image.clone();
+        scaled.resize(width as i32, height as i32);
+        let (source_w, source_h) = (scaled.width.max(1) as usize, scaled.height.max(1) as usize);
+        let data = scaled.get_image_data().to_vec();
+
+        // Nearest lookup also covers a resize that did not reach the exact size
+        Self::from_fn(width, height, |x, y| {
+            let sx = x * source_w / width;
+            let sy = y * source_h / height;
+            data.get(sy * source_w + sx).copied().unwrap_or(Color::MAGENTA)
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // Texel with wrap-around coordinates
+    #[inline]
+    pub fn texel(&self, x: usize, y: usize) -> Color {
+        self.pixels[(y & (self.height - 1)) * self.width + (x & (self.width - 1))]
+    }
+
+    // u, v in [0, 1) cover the texture once; anything outside tiles
+    #[inline]
+    pub fn sample(&self, u: f32, v: f32) -> Color {
+        let x = (u * self.width as f32) as i32 as usize;
+        let y = (v * self.height as f32) as i32 as usize;
+        self.texel(x, y)
+    }
+
+    // Like `sample`, but u, v are held inside [0, 1]: 1.0 is the last column instead of wrapping
+    // to the first one, which would show the opposite edge as a seam along wall edges
+    #[inline]
+    pub fn sample_clamped(&self, u: f32, v: f32) -> Color {
+        let x = ((u * self.width as f32) as i32).clamp(0, self.width as i32 - 1) as usize;
+        let y = ((v * self.height as f32) as i32).clamp(0, self.height as i32 - 1) as usize;
+        self.pixels[y * self.width + x]
+    }
+}
+
+// Floor and ceiling images for a region; `ceiling: None` keeps the open panoramic sky
+#[derive(Debug, Clone, PartialEq)]
+pub struct SurfaceStyle {
+    pub floor: String,
+    pub ceiling: Option<String>,
+}
 
 pub struct TextureManager {
-    pub textures: HashMap<char, Texture2D>,
     pub sky_texture: Option<Texture2D>,
     pub floor_texture: Option<Texture2D>,
     // Cache de píxeles para acceso rápido
-    pub wall_texture_cache: Option<Vec<Vec<Color>>>,
-    pub floor_texture_cache: Option<Vec<Vec<Color>>>,
-    pub sky_texture_cache: Option<Vec<Vec<Color>>>,
+    pub wall_texture_cache: Option<TextureCache>,
+    pub floor_texture_cache: Option<TextureCache>,
+    pub sky_texture_cache: Option<TextureCache>,
+    // Cachés por nombre de archivo para paredes, pisos y techos de cada región
+    pub image_caches: HashMap<String, TextureCache>,
+    // Carácter de pared -> nombre en image_caches
+    pub wall_textures: HashMap<char, String>,
 }
 
 impl TextureManager {
     pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> Self {
-        let mut textures = HashMap::new();
-        
-        // Cargar imagen de pared y crear texturas
-        let wall_texture = Self::load_texture_from_path(rl, thread, "assets/img/wall2.jpg");
-        
-        // Asignar la misma textura a todos los tipos de pared
-        if let Some(texture) = wall_texture {
-            textures.insert('+', texture);
-        } else {
-            // Fallback a textura de color si no se puede cargar la imagen
-            textures.insert('+', Self::create_color_texture(rl, thread, Color::BROWN, 64, 64));
-        }
-        
-        // Para las otras paredes, clonamos la misma textura o usamos fallbacks
-        if let Some(wall_tex) = textures.get(&'+') {
-            // Como no podemos clonar Texture2D directamente, usamos la misma textura para todas
-            // o cargamos la misma imagen múltiples veces
-            let wall_texture2 = Self::load_texture_from_path(rl, thread, "assets/img/wall2.jpg");
-            let wall_texture3 = Self::load_texture_from_path(rl, thread, "assets/img/wall2.jpg");
-            
-            textures.insert('-', wall_texture2.unwrap_or_else(|| Self::create_color_texture(rl, thread, Color::DARKBROWN, 64, 64)));
-            textures.insert('|', wall_texture3.unwrap_or_else(|| Self::create_color_texture(rl, thread, Color::GRAY, 64, 64)));
-        }
-        
         // Cargar texturas para cielo y piso
         let sky_texture = Self::load_texture_from_path(rl, thread, "assets/img/wall1.jpg");
         let floor_texture = Self::load_texture_from_path(rl, thread, "assets/img/wall3.jpg");
         
         let mut manager = TextureManager { 
-            textures,
             sky_texture,
             floor_texture,
             wall_texture_cache: None,
             floor_texture_cache: None,
             sky_texture_cache: None,
+            image_caches: HashMap::new(),
+            wall_textures: HashMap::new(),
         };
         
         // Crear cachés de píxeles para acceso rápido desde las imágenes reales
-        manager.create_texture_caches_from_images(rl, thread);
+        manager.create_texture_caches_from_images();
+        manager.set_wall_textures(&BTreeMap::new());
         
         manager
     }
     
-    fn create_color_texture(rl: &mut RaylibHandle, thread: &RaylibThread, color: Color, width: i32, height: i32) -> Texture2D {
-        let image = Image::gen_image_color(width, height, color);
-        rl.load_texture_from_image(thread, &image).unwrap()
+    /// Sin ventana ni archivos: sólo las texturas generadas en código, así que el
+    /// resultado es el mismo en cualquier máquina. Las imágenes que se pidan después con
+    /// `set_wall_textures` o `preload_image` sí se leen de `IMAGE_DIR`.
+    pub fn headless() -> Self {
+        let mut manager = TextureManager {
+            sky_texture: None,
+            floor_texture: None,
+            wall_texture_cache: Some(fallback_wall_cache()),
+            floor_texture_cache: Some(fallback_floor_cache()),
+            sky_texture_cache: Some(fallback_sky_cache()),
+            image_caches: HashMap::new(),
+            wall_textures: HashMap::new(),
+        };
+        // Image walls stay mapped but unloaded, so they use the default wall cache
+        for &(wall, name) in &DEFAULT_WALL_TEXTURES {
+            if let Some(cache) = procedural_texture(name) {
+                manager.image_caches.insert(name.to_string(), cache);
+            }
+            manager.wall_textures.insert(wall, name.to_string());
+        }
+        manager
     }
-    
+
     fn load_texture_from_path(rl: &mut RaylibHandle, thread: &RaylibThread, path: &str) -> Option<Texture2D> {
         match rl.load_texture(thread, path) {
             Ok(texture) => {
@@ -74,32 +171,18 @@ impl TextureManager {
         }
     }
     
-    fn create_texture_caches_from_images(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+    // Default wall/floor/sky caches from the images, or the generated stand-ins; needs no window
+    pub fn create_texture_caches_from_images(&mut self) {
         // Crear cache para textura de pared desde la imagen real
         match Image::load_image("assets/img/wall2.jpg") {
             Ok(wall_image) => {
                 println!("Creando cache de textura de pared desde wall2.jpg");
-                self.wall_texture_cache = Some(Self::create_pixel_cache(&wall_image));
+                self.wall_texture_cache = Some(TextureCache::from_image(&wall_image));
             },
             Err(e) => {
                 println!("No se pudo cargar wall2.jpg: {}, usando textura procedural para paredes", e);
                 // Fallback a textura procedural
-                let mut cache = Vec::new();
-                for y in 0..64 {
-                    let mut row = Vec::new();
-                    for x in 0..64 {
-                        let intensity = ((x + y) % 16) as f32 / 16.0;
-                        let color = Color::new(
-                            (120.0 + intensity * 50.0) as u8,
-                            (80.0 + intensity * 30.0) as u8,
-                            (40.0 + intensity * 20.0) as u8,
-                            255
-                        );
-                        row.push(color);
-                    }
-                    cache.push(row);
-                }
-                self.wall_texture_cache = Some(cache);
+                self.wall_texture_cache = Some(fallback_wall_cache());
             }
         }
         
@@ -107,27 +190,12 @@ impl TextureManager {
         match Image::load_image("assets/img/wall3.jpg") {
             Ok(floor_image) => {
                 println!("Creando cache de textura de piso desde wall3.jpg");
-                self.floor_texture_cache = Some(Self::create_pixel_cache(&floor_image));
+                self.floor_texture_cache = Some(TextureCache::from_image(&floor_image));
             },
             Err(e) => {
                 println!("No se pudo cargar wall3.jpg: {}, usando textura procedural para piso", e);
                 // Fallback a textura procedural
-                let mut cache = Vec::new();
-                for y in 0..64 {
-                    let mut row = Vec::new();
-                    for x in 0..64 {
-                        let intensity = ((x * 2 + y) % 12) as f32 / 12.0;
-                        let color = Color::new(
-                            (60.0 + intensity * 40.0) as u8,
-                            (100.0 + intensity * 50.0) as u8,
-                            (30.0 + intensity * 25.0) as u8,
-                            255
-                        );
-                        row.push(color);
-                    }
-                    cache.push(row);
-                }
-                self.floor_texture_cache = Some(cache);
+                self.floor_texture_cache = Some(fallback_floor_cache());
             }
         }
         
@@ -135,51 +203,40 @@ impl TextureManager {
         match Image::load_image("assets/img/wall1.jpg") {
             Ok(sky_image) => {
                 println!("Creando cache de textura de cielo desde wall1.jpg");
-                self.sky_texture_cache = Some(Self::create_pixel_cache(&sky_image));
+                self.sky_texture_cache = Some(TextureCache::from_image(&sky_image));
             },
             Err(e) => {
                 println!("No se pudo cargar wall1.jpg: {}, usando textura procedural para cielo", e);
                 // Fallback a textura procedural
-                let mut cache = Vec::new();
-                for y in 0..64 {
-                    let mut row = Vec::new();
-                    for x in 0..64 {
-                        let intensity = ((x + y * 2) % 20) as f32 / 20.0;
-                        let color = Color::new(
-                            (135.0 + intensity * 40.0) as u8,
-                            (180.0 + intensity * 40.0) as u8,
-                            (220.0 + intensity * 35.0) as u8,
-                            255
-                        );
-                        row.push(color);
-                    }
-                    cache.push(row);
-                }
-                self.sky_texture_cache = Some(cache);
+                self.sky_texture_cache = Some(fallback_sky_cache());
             }
         }
     }
     
-    fn create_pixel_cache(image: &Image) -> Vec<Vec<Color>> {
-        let mut cache = Vec::new();
-        let mut temp_image = image.clone();
-        
-        for y in 0..image.height {
-            let mut row = Vec::new();
-            for x in 0..image.width {
-                let color = temp_image.get_color(x, y);
-                row.push(color);
+    /// Asigna una textura a cada carácter de pared: las de `overrides` (región o nivel)
+    /// y para el resto `DEFAULT_WALL_TEXTURES`. Cada imagen se carga una sola vez.
+    pub fn set_wall_textures(&mut self, overrides: &BTreeMap<char, String>) {
+        self.wall_textures.clear();
+        for (wall, name) in wall_texture_names(overrides) {
+            if !self.image_caches.contains_key(&name) {
+                match procedural_texture(&name) {
+                    Some(cache) => {
+                        self.image_caches.insert(name.clone(), cache);
+                    }
+                    None => self.preload_image(&name),
+                }
             }
-            cache.push(row);
+            self.wall_textures.insert(wall, name);
         }
-        cache
     }
-    
-    pub fn get_wall_texture_pixel(&self, tx: f32, ty: f32) -> Color {
-        if let Some(ref cache) = self.wall_texture_cache {
-            let tex_x = ((tx * cache[0].len() as f32) as usize).min(cache[0].len() - 1);
-            let tex_y = ((ty * cache.len() as f32) as usize).min(cache.len() - 1);
-            cache[tex_y][tex_x]
+
+    // Texture of one wall char; unmapped chars or images that failed to load use the default wall
+    pub fn get_wall_texture_pixel(&self, wall: char, tx: f32, ty: f32) -> Color {
+        let cache = self.wall_textures.get(&wall).and_then(|name| self.image_caches.get(name));
+        if let Some(cache) = cache {
+            cache.sample_clamped(tx, ty)
+        } else if let Some(ref cache) = self.wall_texture_cache {
+            cache.sample_clamped(tx, ty)
         } else {
             // Fallback a textura procedural si no hay cache
             let r = (tx * 255.0) as u8;
@@ -191,9 +248,7 @@ impl TextureManager {
     
     pub fn get_floor_texture_pixel(&self, tx: f32, ty: f32) -> Color {
         if let Some(ref cache) = self.floor_texture_cache {
-            let tex_x = ((tx * cache[0].len() as f32) as usize).min(cache[0].len() - 1);
-            let tex_y = ((ty * cache.len() as f32) as usize).min(cache.len() - 1);
-            cache[tex_y][tex_x]
+            cache.sample(tx, ty)
         } else {
             // Fallback a color sólido si no hay cache
             Color::new(139, 90, 43, 255) // Color marrón para el piso
@@ -202,16 +257,202 @@ impl TextureManager {
     
     pub fn get_sky_texture_pixel(&self, tx: f32, ty: f32) -> Color {
         if let Some(ref cache) = self.sky_texture_cache {
-            let tex_x = ((tx * cache[0].len() as f32) as usize).min(cache[0].len() - 1);
-            let tex_y = ((ty * cache.len() as f32) as usize).min(cache.len() - 1);
-            cache[tex_y][tex_x]
+            cache.sample(tx, ty)
         } else {
             // Fallback a color de cielo si no hay cache
             Color::new(135, 206, 235, 255) // Color azul cielo
         }
     }
     
-    pub fn get_texture(&self, wall_type: char) -> Option<&Texture2D> {
-        self.textures.get(&wall_type)
+    // Load an image from IMAGE_DIR into the pixel caches once; missing files only log a warning
+    pub fn preload_image(&mut self, name: &str) {
+        if self.image_caches.contains_key(name) {
+            return;
+        }
+        let path = format!("{}/{}", IMAGE_DIR, name);
+        match Image::load_image(&path) {
+            Ok(image) => {
+                println!("Creando cache de textura desde {}", name);
+                self.image_caches.insert(name.to_string(), TextureCache::from_image(&image));
+            },
+            Err(e) => println!("Warning: No se pudo cargar {}: {}", path, e),
+        }
+    }
+
+    pub fn image_cache(&self, name: &str) -> Option<&TextureCache> {
+        self.image_caches.get(name)
+    }
+}
+
+/// Nombre de textura de cada carácter de pared: `DEFAULT_WALL_TEXTURES` con `overrides` encima.
+pub fn wall_texture_names(overrides: &BTreeMap<char, String>) -> BTreeMap<char, String> {
+    let mut mapping: BTreeMap<char, String> = DEFAULT_WALL_TEXTURES
+        .iter()
+        .map(|&(wall, name)| (wall, name.to_string()))
+        .collect();
+    mapping.extend(overrides.iter().map(|(&wall, name)| (wall, name.clone())));
+    mapping
+}
+
+/// Texturas de pared generadas en código: "brick", "mossy" y "goal_door".
+pub fn procedural_texture(name: &str) -> Option<TextureCache> {
+    let pixel: fn(usize, usize) -> Color = match name {
+        "brick" => brick_pixel,
+        "mossy" => mossy_pixel,
+        "goal_door" => goal_door_pixel,
+        _ => return None,
+    };
+    Some(TextureCache::from_fn(PROCEDURAL_SIZE, PROCEDURAL_SIZE, pixel))
+}
+
+// Stand-ins for wall2/wall3/wall1.jpg when the images can't be read
+fn fallback_wall_cache() -> TextureCache {
+    TextureCache::from_fn(64, 64, |x, y| {
+        let intensity = ((x + y) % 16) as f32 / 16.0;
+        Color::new(
+            (120.0 + intensity * 50.0) as u8,
+            (80.0 + intensity * 30.0) as u8,
+            (40.0 + intensity * 20.0) as u8,
+            255
+        )
+    })
+}
+
+fn fallback_floor_cache() -> TextureCache {
+    TextureCache::from_fn(64, 64, |x, y| {
+        let intensity = ((x * 2 + y) % 12) as f32 / 12.0;
+        Color::new(
+            (60.0 + intensity * 40.0) as u8,
+            (100.0 + intensity * 50.0) as u8,
+            (30.0 + intensity * 25.0) as u8,
+            255
+        )
+    })
+}
+
+fn fallback_sky_cache() -> TextureCache {
+    TextureCache::from_fn(64, 64, |x, y| {
+        let intensity = ((x + y * 2) % 20) as f32 / 20.0;
+        Color::new(
+            (135.0 + intensity * 40.0) as u8,
+            (180.0 + intensity * 40.0) as u8,
+            (220.0 + intensity * 35.0) as u8,
+            255
+        )
+    })
+}
+
+// Cheap repeatable per-pixel noise in [0, 1)
+fn noise(x: usize, y: usize) -> f32 {
+    let mut h = (x as u32).wrapping_mul(374_761_393) ^ (y as u32).wrapping_mul(668_265_263);
+    h = (h ^ (h >> 13)).wrapping_mul(1_274_126_177);
+    (h >> 8) as f32 / (1u32 << 24) as f32
+}
+
+fn tint(r: f32, g: f32, b: f32, amount: f32) -> Color {
+    Color::new((r * amount).min(255.0) as u8, (g * amount).min(255.0) as u8, (b * amount).min(255.0) as u8, 255)
+}
+
+// Red bricks in running bond with gray mortar
+fn brick_pixel(x: usize, y: usize) -> Color {
+    let (brick_w, brick_h) = (16, 8);
+    let row = y / brick_h;
+    let offset = if row.is_multiple_of(2) { 0 } else { brick_w / 2 };
+    if y.is_multiple_of(brick_h) || (x + offset).is_multiple_of(brick_w) {
+        return tint(150.0, 145.0, 140.0, 0.85 + 0.15 * noise(x, y));
+    }
+    // Each brick gets its own shade
+    let brick = noise((x + offset) / brick_w, row + 100);
+    tint(165.0, 70.0, 50.0, 0.75 + 0.2 * brick + 0.1 * noise(x, y))
+}
+
+// Stone blocks with moss creeping up from the bottom
+fn mossy_pixel(x: usize, y: usize) -> Color {
+    let block = 32;
+    if y.is_multiple_of(block) || (x + (y / block) * 16).is_multiple_of(block) {
+        return tint(70.0, 70.0, 65.0, 1.0);
+    }
+    let moss_chance = 0.15 + 0.6 * y as f32 / PROCEDURAL_SIZE as f32;
+    // Clumps: neighbouring 4x4 blocks share the decision
+    if noise(x / 4, y / 4 + 500) < moss_chance {
+        tint(60.0, 120.0, 45.0, 0.8 + 0.3 * noise(x, y))
+    } else {
+        tint(125.0, 125.0, 115.0, 0.8 + 0.25 * noise(x, y))
+    }
+}
+
+// Wooden door in a gold frame, the way out of the maze
+fn goal_door_pixel(x: usize, y: usize) -> Color {
+    let size = PROCEDURAL_SIZE;
+    let frame = 5;
+    if x < frame || x >= size - frame || y < frame {
+        return tint(230.0, 185.0, 40.0, 0.85 + 0.15 * noise(x, y));
+    }
+    // Round gold knob on the right
+    let (dx, dy) = (x as f32 - 48.0, y as f32 - 36.0);
+    if dx * dx + dy * dy <= 9.0 {
+        return Color::new(255, 220, 80, 255);
+    }
+    // Vertical planks
+    if (x - frame).is_multiple_of(9) {
+        return tint(70.0, 40.0, 20.0, 1.0);
+    }
+    tint(130.0, 80.0, 40.0, 0.85 + 0.15 * noise(x / 9, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn special_walls_have_generated_textures() {
+        for &(_, name) in &DEFAULT_WALL_TEXTURES {
+            if name.contains('.') {
+                continue;
+            }
+            let texture = procedural_texture(name).unwrap_or_else(|| panic!("{} no existe", name));
+            assert_eq!((texture.width(), texture.height()), (PROCEDURAL_SIZE, PROCEDURAL_SIZE));
+        }
+        assert!(procedural_texture("wall1.jpg").is_none());
+    }
+
+    #[test]
+    fn generated_textures_are_stable() {
+        let a = procedural_texture("brick").unwrap();
+        let b = procedural_texture("brick").unwrap();
+        assert!(a.pixels.iter().zip(&b.pixels).all(|(p, q)| (p.r, p.g, p.b) == (q.r, q.g, q.b)));
+    }
+
+    #[test]
+    fn sampling_wraps_and_indexes_rows() {
+        let cache = TextureCache::from_fn(4, 2, |x, y| Color::new(x as u8, y as u8, 0, 255));
+        let at = |u, v| {
+            let c = cache.sample(u, v);
+            (c.r, c.g)
+        };
+        assert_eq!(at(0.0, 0.0), (0, 0));
+        assert_eq!(at(0.8, 0.6), (3, 1));
+        assert_eq!(at(1.25, 0.0), (1, 0)); // Tiles past 1.0
+        assert_eq!(at(-0.25, 0.0), (3, 0)); // And backwards before 0.0
+    }
+
+    #[test]
+    fn clamped_sampling_stops_at_the_edges() {
+        let cache = TextureCache::from_fn(4, 2, |x, y| Color::new(x as u8, y as u8, 0, 255));
+        let at = |u, v| {
+            let c = cache.sample_clamped(u, v);
+            (c.r, c.g)
+        };
+        assert_eq!(at(0.8, 0.6), (3, 1));
+        assert_eq!(at(1.0, 1.0), (3, 1)); // The wall's far edge, not the first column again
+        assert_eq!(at(-0.1, 0.0), (0, 0));
+        let wrapped = cache.sample(1.0, 0.0);
+        assert_eq!(wrapped.r, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_non_power_of_two() {
+        TextureCache::from_fn(3, 4, |_, _| Color::BLACK);
     }
 }
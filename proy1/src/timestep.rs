@@ -0,0 +1,73 @@
+//! Simulación a paso fijo.
+//!
+//! El movimiento avanza siempre en pasos de `1 / SIMULATION_HZ` segundos, sin importar cuántos
+//! FPS dé la máquina: el tiempo de cada frame se acumula y se consume en pasos enteros. Lo que
+//! sobra (`alpha`) sirve para dibujar a medio camino entre el paso anterior y el actual.
+
+pub const SIMULATION_HZ: f32 = 120.0;
+// Longest frame fed to the simulation; after a hitch the game slows down instead of spiraling
+const MAX_FRAME_TIME: f32 = 0.25;
+// Rounding slack, as a fraction of a step
+const STEP_EPSILON: f32 = 1e-3;
+
+pub struct FixedTimestep {
+    step: f32,        // Seconds per simulation step
+    accumulator: f32, // Frame time not simulated yet, always under one step after advance()
+}
+
+impl FixedTimestep {
+    pub fn new(hz: f32) -> Self {
+        FixedTimestep { step: 1.0 / hz, accumulator: 0.0 }
+    }
+
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    /// Suma el tiempo del último frame y devuelve cuántos pasos de simulación hay que correr.
+    pub fn advance(&mut self, frame_time: f32) -> u32 {
+        self.accumulator += frame_time.clamp(0.0, MAX_FRAME_TIME);
+        // A frame of exactly two steps can come out as 1.99999 in f32; don't leave it for the next one
+        let steps = (self.accumulator / self.step + STEP_EPSILON).floor();
+        self.accumulator = (self.accumulator - steps * self.step).max(0.0);
+        steps as u32
+    }
+
+    // Share of the next step already elapsed: 0.0 draws the previous step, 1.0 the current one
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.step).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_time_is_consumed_in_whole_steps() {
+        let mut clock = FixedTimestep::new(120.0);
+        // 60 FPS: two steps per frame
+        assert_eq!(clock.advance(1.0 / 60.0), 2);
+        // 144 FPS: mostly one step, sometimes none, and the remainder carries over
+        let steps: u32 = (0..144).map(|_| clock.advance(1.0 / 144.0)).sum();
+        assert!((119..=121).contains(&steps), "{} pasos en un segundo", steps);
+        assert!(clock.alpha() < 1.0);
+    }
+
+    #[test]
+    fn alpha_is_the_leftover_fraction() {
+        let mut clock = FixedTimestep::new(100.0);
+        assert_eq!(clock.advance(0.025), 2);
+        assert!((clock.alpha() - 0.5).abs() < 1e-3);
+        assert_eq!(clock.advance(0.0), 0);
+        assert_eq!(clock.advance(0.005), 1);
+        assert!(clock.alpha() < 1e-3);
+    }
+
+    #[test]
+    fn long_frames_are_capped() {
+        let mut clock = FixedTimestep::new(120.0);
+        assert_eq!(clock.advance(5.0), (MAX_FRAME_TIME * 120.0).round() as u32);
+        assert_eq!(clock.advance(-1.0), 0);
+    }
+}
@@ -0,0 +1,102 @@
+//! Interfaz independiente de la resolución.
+//!
+//! Los menús y el HUD se diseñaron sobre 800x600. Los menús se dibujan en esas
+//! coordenadas a través de una cámara 2D que los escala y centra; el HUD se pega a
+//! los bordes de la pantalla y sólo escala tamaños y márgenes.
+
+use raylib::prelude::*;
+
+pub const DESIGN_WIDTH: i32 = 800;
+pub const DESIGN_HEIGHT: i32 = 600;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    pub width: i32,  // Screen size in pixels
+    pub height: i32,
+    pub scale: f32,  // Screen pixels per design pixel, the same on both axes
+}
+
+impl Layout {
+    pub fn new(width: i32, height: i32) -> Self {
+        let scale = (width as f32 / DESIGN_WIDTH as f32).min(height as f32 / DESIGN_HEIGHT as f32);
+        Layout { width, height, scale: scale.max(0.01) }
+    }
+
+    pub fn of(rl: &RaylibHandle) -> Self {
+        Self::new(rl.get_screen_width(), rl.get_screen_height())
+    }
+
+    /// Cámara que lleva el área de diseño de 800x600 al centro de la pantalla sin deformarla.
+    pub fn camera(&self) -> Camera2D {
+        let area = self.design_area();
+        Camera2D {
+            offset: Vector2::new(-area.x * self.scale, -area.y * self.scale),
+            target: Vector2::zero(),
+            rotation: 0.0,
+            zoom: self.scale,
+        }
+    }
+
+    // Whole screen in design coordinates; wider than 0..800 (or taller than 0..600) when letterboxed
+    pub fn design_area(&self) -> Rectangle {
+        let width = self.width as f32 / self.scale;
+        let height = self.height as f32 / self.scale;
+        Rectangle::new(
+            (DESIGN_WIDTH as f32 - width) / 2.0,
+            (DESIGN_HEIGHT as f32 - height) / 2.0,
+            width,
+            height,
+        )
+    }
+
+    // Design length (margin, box size) in screen pixels
+    pub fn px(&self, length: i32) -> i32 {
+        (length as f32 * self.scale).round() as i32
+    }
+
+    // Font sizes shrink with the window but stay readable
+    pub fn font(&self, size: i32) -> i32 {
+        self.px(size).max(10)
+    }
+
+    // Distance from the right/bottom edge, given in design pixels
+    pub fn right(&self, offset: i32) -> i32 {
+        self.width - self.px(offset)
+    }
+
+    pub fn bottom(&self, offset: i32) -> i32 {
+        self.height - self.px(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn design_size_is_identity() {
+        let ui = Layout::new(DESIGN_WIDTH, DESIGN_HEIGHT);
+        assert_eq!(ui.scale, 1.0);
+        assert_eq!(ui.design_area(), Rectangle::new(0.0, 0.0, 800.0, 600.0));
+        assert_eq!((ui.px(10), ui.right(10), ui.bottom(30)), (10, 790, 570));
+    }
+
+    #[test]
+    fn widescreen_is_pillarboxed() {
+        let ui = Layout::new(1280, 720);
+        assert_eq!(ui.scale, 1.2);
+        // 1280 / 1.2 = 1066.67 design pixels across, centered on 0..800
+        let area = ui.design_area();
+        assert!((area.x + 133.33).abs() < 0.01 && area.y == 0.0);
+        let camera = ui.camera();
+        assert!((camera.offset.x - 160.0).abs() < 0.01 && camera.offset.y == 0.0);
+        assert_eq!((ui.font(20), ui.right(10)), (24, 1268));
+    }
+
+    #[test]
+    fn small_windows_keep_fonts_readable() {
+        let ui = Layout::new(320, 240);
+        assert_eq!(ui.scale, 0.4);
+        assert_eq!(ui.font(14), 10);
+    }
+}
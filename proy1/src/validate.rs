@@ -0,0 +1,265 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::maze::is_wall_cell;
+
+/// Problema encontrado al revisar un laberinto. Las coordenadas son (columna, fila) en `Maze::map`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MazeError {
+    Empty,
+    RaggedRow { row: usize, len: usize, expected: usize },
+    MissingStart,
+    DuplicateStart { count: usize },
+    MissingGoal,
+    DuplicateGoal { count: usize },
+    UnknownChar { x: usize, y: usize, ch: char },
+    UnreachableGoal,
+    OpenBorder { x: usize, y: usize },
+}
+
+impl fmt::Display for MazeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MazeError::Empty => write!(f, "el laberinto está vacío"),
+            MazeError::RaggedRow { row, len, expected } => {
+                write!(f, "la fila {} mide {} en vez de {}", row, len, expected)
+            }
+            MazeError::MissingStart => write!(f, "falta la salida 'p'"),
+            MazeError::DuplicateStart { count } => write!(f, "hay {} salidas 'p'", count),
+            MazeError::MissingGoal => write!(f, "falta la meta 'g'"),
+            MazeError::DuplicateGoal { count } => write!(f, "hay {} metas 'g'", count),
+            MazeError::UnknownChar { x, y, ch } => write!(f, "carácter desconocido {:?} en ({}, {})", ch, x, y),
+            MazeError::UnreachableGoal => write!(f, "la meta no se puede alcanzar desde la salida"),
+            MazeError::OpenBorder { x, y } => write!(f, "borde abierto en ({}, {})", x, y),
+        }
+    }
+}
+
+// One line for logs and error messages
+pub fn describe(errors: &[MazeError]) -> String {
+    errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ")
+}
+
+// Everything a level may contain: walls, floor, start and goal
+pub fn is_known_cell(cell: char) -> bool {
+    is_wall_cell(cell) || matches!(cell, ' ' | 'p' | 'g')
+}
+
+// All problems at once, in reading order, so a level author can fix them in one pass
+pub fn validate(map: &[Vec<char>]) -> Vec<MazeError> {
+    let width = match map.first() {
+        Some(row) if !row.is_empty() => row.len(),
+        _ => return vec![MazeError::Empty],
+    };
+
+    let mut errors = Vec::new();
+
+    for (y, row) in map.iter().enumerate() {
+        if row.len() != width {
+            errors.push(MazeError::RaggedRow { row: y, len: row.len(), expected: width });
+        }
+    }
+
+    let starts = find_all(map, 'p');
+    let goals = find_all(map, 'g');
+    match starts.len() {
+        0 => errors.push(MazeError::MissingStart),
+        1 => {}
+        count => errors.push(MazeError::DuplicateStart { count }),
+    }
+    match goals.len() {
+        0 => errors.push(MazeError::MissingGoal),
+        1 => {}
+        count => errors.push(MazeError::DuplicateGoal { count }),
+    }
+
+    for (y, row) in map.iter().enumerate() {
+        for (x, &ch) in row.iter().enumerate() {
+            if !is_known_cell(ch) {
+                errors.push(MazeError::UnknownChar { x, y, ch });
+            }
+        }
+    }
+
+    for (x, y) in border_cells(map) {
+        if !is_wall_cell(map[y][x]) {
+            errors.push(MazeError::OpenBorder { x, y });
+        }
+    }
+
+    if let (Some(&start), Some(&(gx, gy))) = (starts.first(), goals.first())
+        && distances_from(map, start)[gy][gx].is_none()
+    {
+        errors.push(MazeError::UnreachableGoal);
+    }
+
+    errors
+}
+
+/// Corrige lo que se pueda sin rediseñar el nivel y devuelve los problemas que tenía.
+///
+/// Las filas cortas se rellenan con pared, los caracteres desconocidos pasan a suelo,
+/// el borde se cierra, sobran 'p'/'g' se borran y la meta se mueve a la celda
+/// alcanzable más lejana si falta o no tiene camino. Un mapa vacío no tiene arreglo.
+pub fn repair(map: &mut [Vec<char>]) -> Vec<MazeError> {
+    let errors = validate(map);
+    if errors.is_empty() || errors.contains(&MazeError::Empty) {
+        return errors;
+    }
+
+    let width = map.iter().map(|row| row.len()).max().unwrap_or(0);
+    for row in map.iter_mut() {
+        row.resize(width, '+');
+        for cell in row.iter_mut() {
+            if !is_known_cell(*cell) {
+                *cell = ' ';
+            }
+        }
+    }
+
+    let last_row = map.len() - 1;
+    for (x, y) in border_cells(map) {
+        if !is_wall_cell(map[y][x]) {
+            map[y][x] = if y == 0 || y == last_row { '-' } else { '|' };
+        }
+    }
+
+    // Keep only the first start and goal
+    for target in ['p', 'g'] {
+        for &(x, y) in find_all(map, target).iter().skip(1) {
+            map[y][x] = ' ';
+        }
+    }
+
+    let start = match find_all(map, 'p').first() {
+        Some(&start) => start,
+        None => match find_all(map, ' ').first() {
+            Some(&(x, y)) => {
+                map[y][x] = 'p';
+                (x, y)
+            }
+            None => return errors, // No floor left to stand on
+        },
+    };
+
+    let distances = distances_from(map, start);
+    let goal_reachable = find_all(map, 'g').first().is_some_and(|&(x, y)| distances[y][x].is_some());
+    if !goal_reachable {
+        let farthest = distances
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter().enumerate().filter_map(move |(x, d)| d.map(|d| (d, x, y))))
+            .filter(|&(d, _, _)| d > 0)
+            .max_by_key(|&(d, x, y)| (d, std::cmp::Reverse((y, x))));
+
+        if let Some((_, x, y)) = farthest {
+            for (gx, gy) in find_all(map, 'g') {
+                map[gy][gx] = ' ';
+            }
+            map[y][x] = 'g';
+        }
+    }
+
+    errors
+}
+
+fn find_all(map: &[Vec<char>], target: char) -> Vec<(usize, usize)> {
+    map.iter()
+        .enumerate()
+        .flat_map(|(y, row)| row.iter().enumerate().filter(move |(_, c)| **c == target).map(move |(x, _)| (x, y)))
+        .collect()
+}
+
+// First/last row plus the first/last char of every row in between
+fn border_cells(map: &[Vec<char>]) -> Vec<(usize, usize)> {
+    let last_row = map.len().saturating_sub(1);
+    let mut cells = Vec::new();
+    for (y, row) in map.iter().enumerate() {
+        for x in 0..row.len() {
+            if y == 0 || y == last_row || x == 0 || x == row.len() - 1 {
+                cells.push((x, y));
+            }
+        }
+    }
+    cells
+}
+
+/// Pasos (BFS) desde `start` hasta cada celda abierta; `None` para paredes y celdas inalcanzables.
+pub fn distances_from(map: &[Vec<char>], start: (usize, usize)) -> Vec<Vec<Option<usize>>> {
+    let mut distances: Vec<Vec<Option<usize>>> = map.iter().map(|row| vec![None; row.len()]).collect();
+    distances[start.1][start.0] = Some(0);
+    let mut queue = VecDeque::from([(start, 0)]);
+
+    while let Some(((x, y), d)) = queue.pop_front() {
+        let neighbors = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        for (nx, ny) in neighbors {
+            let open = map.get(ny).and_then(|row| row.get(nx)).is_some_and(|&c| !is_wall_cell(c));
+            if open && distances[ny][nx].is_none() {
+                distances[ny][nx] = Some(d + 1);
+                queue.push_back(((nx, ny), d + 1));
+            }
+        }
+    }
+
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Maze;
+
+    #[test]
+    fn accepts_a_good_maze() {
+        let map = Maze::from_rows(&["+--+--+", "|p    |", "+--+  +", "|    g|", "+--+--+"]).map;
+        assert_eq!(validate(&map), []);
+    }
+
+    #[test]
+    fn reports_each_problem() {
+        assert_eq!(validate(&[]), [MazeError::Empty]);
+
+        let ragged = Maze::from_rows(&["+--+", "|pg|", "+-+"]).map;
+        assert_eq!(validate(&ragged), [MazeError::RaggedRow { row: 2, len: 3, expected: 4 }]);
+
+        let no_goal = Maze::from_rows(&["+--+", "|p |", "+--+"]).map;
+        assert_eq!(validate(&no_goal), [MazeError::MissingGoal]);
+
+        let two_starts = Maze::from_rows(&["+---+", "|ppg|", "+---+"]).map;
+        assert_eq!(validate(&two_starts), [MazeError::DuplicateStart { count: 2 }]);
+
+        let unknown = Maze::from_rows(&["+---+", "|p#g|", "+---+"]).map;
+        assert_eq!(validate(&unknown), [MazeError::UnknownChar { x: 2, y: 1, ch: '#' }]);
+
+        let walled_off = Maze::from_rows(&["+---+", "|p|g|", "+---+"]).map;
+        assert_eq!(validate(&walled_off), [MazeError::UnreachableGoal]);
+
+        let leaky = Maze::from_rows(&["+- -+", "|p g|", "+---+"]).map;
+        assert_eq!(validate(&leaky), [MazeError::OpenBorder { x: 2, y: 0 }]);
+    }
+
+    #[test]
+    fn repair_fixes_everything_it_reports() {
+        let mut map = Maze::from_rows(&["+- -+--", "|p#|  g", "|  | p|", "+--+"]).map;
+        let found = repair(&mut map);
+        assert!(found.contains(&MazeError::UnreachableGoal));
+        assert!(found.contains(&MazeError::DuplicateStart { count: 2 }));
+        assert_eq!(validate(&map), []);
+        // The first 'p' survives and the goal lands on the farthest reachable cell
+        assert_eq!(map[1][1], 'p');
+        assert_eq!(find_all(&map, 'g').len(), 1);
+    }
+
+    #[test]
+    fn repair_leaves_good_maze_untouched() {
+        let mut map = Maze::from_rows(&["+--+--+", "|p    |", "+--+  +", "|    g|", "+--+--+"]).map;
+        let before = map.clone();
+        assert_eq!(repair(&mut map), []);
+        assert_eq!(map, before);
+    }
+}